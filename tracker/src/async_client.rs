@@ -0,0 +1,422 @@
+use crate::bitcoin_client::{BitcoinClient, ClientError};
+use async_trait::async_trait;
+use bitcoin::{Block, BlockHash, Txid};
+use bitcoincore_rpc::json::{GetBlockHeaderResult, GetBlockchainInfoResult};
+use bitcoincore_rpc::RawTx;
+use std::error::Error;
+use std::ops::ControlFlow;
+use std::time::{Duration, SystemTime};
+use tokio::task::spawn_blocking;
+
+/// Async counterpart of [`BitcoinClient`](crate::bitcoin_client::BitcoinClient), for
+/// deployments that poll a remote node from inside a Tokio service rather than a dedicated
+/// blocking thread.
+#[async_trait]
+pub trait AsyncBitcoinClient {
+    type Err: Error + Send + Sync + 'static;
+
+    async fn get_blockchain_info(
+        &self,
+    ) -> Result<GetBlockchainInfoResult, ClientError<Self::Err>>;
+    async fn get_block_hash(&self, height: u64) -> Result<BlockHash, ClientError<Self::Err>>;
+    async fn get_block_header_info(
+        &self,
+        hash: &BlockHash,
+    ) -> Result<GetBlockHeaderResult, ClientError<Self::Err>>;
+    async fn get_block(&self, hash: &BlockHash) -> Result<Block, ClientError<Self::Err>>;
+    async fn send_raw_transaction<R: RawTx + Send>(
+        &self,
+        tx: R,
+    ) -> Result<Txid, ClientError<Self::Err>>;
+}
+
+/// Whether an error is worth retrying. Connection/timeout failures are transient;
+/// consensus/validation failures are permanent and should fail fast.
+pub trait RetryableError {
+    fn is_transient(&self) -> bool;
+}
+
+/// Bounded exponential backoff with jitter.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Total attempts, including the first.
+    pub max_attempts: u32,
+    /// Delay before the second attempt; doubles every retry up to `max_delay`.
+    pub base_delay: Duration,
+    /// Upper bound for a single delay.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryConfig {
+    // Delay after failed attempt `attempt` (1-based), i.e. before attempt `attempt + 1`:
+    // base * 2^(attempt-1), capped, with up to
+    // 50% jitter so a fleet of pollers does not reconnect in lockstep.
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.min(20).saturating_sub(1);
+        let raw = self.base_delay.saturating_mul(factor as u32).min(self.max_delay);
+        let jitter = (raw.as_millis() as u64 / 2).max(1);
+        raw.saturating_sub(Duration::from_millis(pseudo_random(jitter)))
+    }
+}
+
+// A cheap, dependency-free jitter source: low bits of the wall clock. It only needs to
+// differ between processes, not to be cryptographically random.
+fn pseudo_random(bound: u64) -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    nanos % bound
+}
+
+/// Wraps an [`AsyncBitcoinClient`] and retries transient failures with backoff.
+#[derive(Debug, Clone)]
+pub struct Retrying<C> {
+    inner: C,
+    config: RetryConfig,
+}
+
+impl<C> Retrying<C> {
+    pub fn new(inner: C, config: RetryConfig) -> Self {
+        Retrying { inner, config }
+    }
+
+    async fn retry<T, F, Fut>(&self, mut op: F) -> Result<T, ClientError<C::Err>>
+    where
+        C: AsyncBitcoinClient,
+        C::Err: RetryableError,
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, ClientError<C::Err>>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Ok(value) => return Ok(value),
+                Err(err) if attempt < self.config.max_attempts && err.0.is_transient() => {
+                    tokio::time::sleep(self.config.delay_for(attempt)).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<C> AsyncBitcoinClient for Retrying<C>
+where
+    C: AsyncBitcoinClient + Sync + Send,
+    C::Err: RetryableError,
+{
+    type Err = C::Err;
+
+    async fn get_blockchain_info(
+        &self,
+    ) -> Result<GetBlockchainInfoResult, ClientError<Self::Err>> {
+        self.retry(|| self.inner.get_blockchain_info()).await
+    }
+
+    async fn get_block_hash(&self, height: u64) -> Result<BlockHash, ClientError<Self::Err>> {
+        self.retry(|| self.inner.get_block_hash(height)).await
+    }
+
+    async fn get_block_header_info(
+        &self,
+        hash: &BlockHash,
+    ) -> Result<GetBlockHeaderResult, ClientError<Self::Err>> {
+        self.retry(|| self.inner.get_block_header_info(hash)).await
+    }
+
+    async fn get_block(&self, hash: &BlockHash) -> Result<Block, ClientError<Self::Err>> {
+        self.retry(|| self.inner.get_block(hash)).await
+    }
+
+    async fn send_raw_transaction<R: RawTx + Send>(
+        &self,
+        tx: R,
+    ) -> Result<Txid, ClientError<Self::Err>> {
+        // Rebroadcasting is idempotent at the txid level, so a transient send failure is safe
+        // to retry; a permanent rejection (already-known, invalid) classifies as non-transient
+        // and fails fast. Serialize once so the retried op can resend the same bytes.
+        let hex = tx.raw_hex();
+        self.retry(|| self.inner.send_raw_transaction(hex.clone())).await
+    }
+}
+
+/// Bridges a blocking [`BitcoinClient`] into an [`AsyncBitcoinClient`] by running each call on
+/// the Tokio blocking pool. It lets an async service reuse the existing synchronous backends
+/// (e.g. [`EsploraClient`](crate::esplora::EsploraClient) or a `bitcoincore_rpc` client) without
+/// a second, async HTTP stack; wrap it in [`Retrying`] to get bounded backoff on top.
+#[derive(Debug, Clone)]
+pub struct BlockingAsync<C> {
+    inner: C,
+}
+
+impl<C> BlockingAsync<C> {
+    pub fn new(inner: C) -> Self {
+        BlockingAsync { inner }
+    }
+}
+
+#[async_trait]
+impl<C> AsyncBitcoinClient for BlockingAsync<C>
+where
+    C: BitcoinClient + Clone + Send + Sync + 'static,
+    C::Err: Send + Sync + 'static,
+{
+    type Err = C::Err;
+
+    async fn get_blockchain_info(
+        &self,
+    ) -> Result<GetBlockchainInfoResult, ClientError<Self::Err>> {
+        let client = self.inner.clone();
+        spawn_blocking(move || client.get_blockchain_info())
+            .await
+            .expect("blocking bitcoin client task panicked")
+    }
+
+    async fn get_block_hash(&self, height: u64) -> Result<BlockHash, ClientError<Self::Err>> {
+        let client = self.inner.clone();
+        spawn_blocking(move || client.get_block_hash(height))
+            .await
+            .expect("blocking bitcoin client task panicked")
+    }
+
+    async fn get_block_header_info(
+        &self,
+        hash: &BlockHash,
+    ) -> Result<GetBlockHeaderResult, ClientError<Self::Err>> {
+        let client = self.inner.clone();
+        let hash = *hash;
+        spawn_blocking(move || client.get_block_header_info(&hash))
+            .await
+            .expect("blocking bitcoin client task panicked")
+    }
+
+    async fn get_block(&self, hash: &BlockHash) -> Result<Block, ClientError<Self::Err>> {
+        let client = self.inner.clone();
+        let hash = *hash;
+        spawn_blocking(move || client.get_block(&hash))
+            .await
+            .expect("blocking bitcoin client task panicked")
+    }
+
+    async fn send_raw_transaction<R: RawTx + Send>(
+        &self,
+        tx: R,
+    ) -> Result<Txid, ClientError<Self::Err>> {
+        let client = self.inner.clone();
+        // Serialize on the caller's task so the blocking closure owns a `'static` payload.
+        let hex = tx.raw_hex();
+        spawn_blocking(move || client.send_raw_transaction(hex))
+            .await
+            .expect("blocking bitcoin client task panicked")
+    }
+}
+
+#[cfg(feature = "esplora")]
+impl RetryableError for crate::esplora::EsploraError {
+    fn is_transient(&self) -> bool {
+        use crate::esplora::EsploraError;
+        match self {
+            // Connection resets, timeouts, 429s and 5xx are worth retrying; a 4xx, a malformed
+            // body or an unsupported operation will not change on a retry.
+            EsploraError::Http(err) => match err.as_ref() {
+                ureq::Error::Status(code, _) => *code == 429 || (500..600).contains(code),
+                ureq::Error::Transport(_) => true,
+            },
+            EsploraError::Io(_) => true,
+            EsploraError::Malformed | EsploraError::Unsupported(_) => false,
+        }
+    }
+}
+
+/// Drive headers-first catch-up from an [`AsyncBitcoinClient`] inside a Tokio service.
+///
+/// Polls the node tip and, for every new block at or above `next_height`, fetches it in order
+/// and hands it to `on_block`, then sleeps `poll_interval` and repeats. This is the async
+/// counterpart of [`Index::add_btc_blocks`](crate::Index): the blocking `Index` keeps owning
+/// bid storage while this loop supplies ordered blocks to a handler that forwards them into it,
+/// so a deployment can poll a flaky remote node from async code. Wrap `client` in [`Retrying`]
+/// to get bounded backoff on every request. `on_block` returns [`ControlFlow::Break`] to stop
+/// the loop (returning `Ok(())`); otherwise the loop only returns when a client call fails,
+/// which for a [`Retrying`] client means a non-transient error or exhausted retries.
+pub async fn sync_loop<C, F>(
+    client: &C,
+    mut next_height: u64,
+    poll_interval: Duration,
+    mut on_block: F,
+) -> Result<(), ClientError<C::Err>>
+where
+    C: AsyncBitcoinClient + Sync,
+    F: FnMut(u64, Block) -> ControlFlow<()>,
+{
+    loop {
+        let tip = client.get_blockchain_info().await?.blocks;
+        while next_height <= tip {
+            let hash = client.get_block_hash(next_height).await?;
+            let block = client.get_block(&hash).await?;
+            if on_block(next_height, block).is_break() {
+                return Ok(());
+            }
+            next_height += 1;
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::{sha256d, Hash};
+    use bitcoin::BlockHeader;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug)]
+    struct MockError {
+        transient: bool,
+    }
+
+    impl std::fmt::Display for MockError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "mock error (transient={})", self.transient)
+        }
+    }
+    impl std::error::Error for MockError {}
+
+    impl RetryableError for MockError {
+        fn is_transient(&self) -> bool {
+            self.transient
+        }
+    }
+
+    fn hash_at(height: u64) -> BlockHash {
+        BlockHash::from_hash(sha256d::Hash::hash(&height.to_le_bytes()))
+    }
+
+    fn info(tip: u64) -> GetBlockchainInfoResult {
+        GetBlockchainInfoResult {
+            chain: String::new(),
+            blocks: tip,
+            headers: tip,
+            best_block_hash: hash_at(tip),
+            difficulty: 0.0,
+            median_time: 0,
+            verification_progress: 1.0,
+            initial_block_download: false,
+            chain_work: vec![],
+            size_on_disk: 0,
+            pruned: false,
+            prune_height: None,
+            automatic_pruning: None,
+            prune_target_size: None,
+            softforks: Default::default(),
+            warnings: String::new(),
+        }
+    }
+
+    fn empty_block() -> Block {
+        Block {
+            header: BlockHeader {
+                version: 0,
+                prev_blockhash: Default::default(),
+                merkle_root: Default::default(),
+                time: 0,
+                bits: 0,
+                nonce: 0,
+            },
+            txdata: vec![],
+        }
+    }
+
+    struct MockNode {
+        tip: u64,
+        // Number of leading `get_blockchain_info` calls that fail transiently before succeeding.
+        transient_failures: AtomicU32,
+    }
+
+    #[async_trait]
+    impl AsyncBitcoinClient for MockNode {
+        type Err = MockError;
+
+        async fn get_blockchain_info(
+            &self,
+        ) -> Result<GetBlockchainInfoResult, ClientError<Self::Err>> {
+            if self
+                .transient_failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(ClientError(MockError { transient: true }));
+            }
+            Ok(info(self.tip))
+        }
+
+        async fn get_block_hash(
+            &self,
+            height: u64,
+        ) -> Result<BlockHash, ClientError<Self::Err>> {
+            Ok(hash_at(height))
+        }
+
+        async fn get_block_header_info(
+            &self,
+            _hash: &BlockHash,
+        ) -> Result<GetBlockHeaderResult, ClientError<Self::Err>> {
+            unimplemented!()
+        }
+
+        async fn get_block(&self, _hash: &BlockHash) -> Result<Block, ClientError<Self::Err>> {
+            Ok(empty_block())
+        }
+
+        async fn send_raw_transaction<R: RawTx + Send>(
+            &self,
+            _tx: R,
+        ) -> Result<Txid, ClientError<Self::Err>> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn retrying_sync_loop_delivers_blocks_in_order_and_retries_transient_errors() {
+        let node = Retrying::new(
+            MockNode {
+                tip: 5,
+                // The first two tip polls fail transiently and must be retried transparently.
+                transient_failures: AtomicU32::new(2),
+            },
+            RetryConfig {
+                max_attempts: 5,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(5),
+            },
+        );
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_in_loop = Arc::clone(&seen);
+        sync_loop(&node, 1, Duration::from_millis(1), move |height, _block| {
+            seen_in_loop.lock().unwrap().push(height);
+            if height == 5 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .await
+        .expect("sync loop should retry transient failures and complete");
+
+        assert_eq!(*seen.lock().unwrap(), vec![1, 2, 3, 4, 5]);
+    }
+}
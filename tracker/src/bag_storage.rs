@@ -19,6 +19,10 @@ pub trait BagStorage {
     ) -> Result<(), Self::Err>;
     fn delete_bag(&self, bag: &BagId) -> Result<(), Self::Err>;
     fn is_bag_exists(&self, bag: &BagId) -> Result<bool, Self::Err>;
+    /// Whether the bag has been stored as confirmed. This flips true the moment the bag is
+    /// mined and is **not** gated by the confirmation-depth safety margin, so near the tip it
+    /// can report a bag that a reorg may still orphan; callers needing reorg safety must use
+    /// [`Index::confirmed_bags`](crate::Index::confirmed_bags) instead.
     fn is_bag_confirmed(&self, bag: &BagId) -> Result<bool, Self::Err>;
     fn count_bags(&self) -> Result<u64, Self::Err>;
 }
@@ -1,4 +1,5 @@
 use crate::bag_id::BagId;
+use crate::compact_filter::{block_filter, BlockFilter};
 use crate::record::{BidTx, Outpoint};
 use bitcoin::blockdata::script;
 use bitcoin::consensus::Encodable;
@@ -26,6 +27,20 @@ pub trait BitcoinClient {
         hash: &BlockHash,
     ) -> Result<GetBlockHeaderResult, ClientError<Self::Err>>;
     fn get_block(&self, hash: &BlockHash) -> Result<Block, ClientError<Self::Err>>;
+    /// Fetch a batch of blocks. The default fetches them one by one; backends that can
+    /// pipeline requests should override this to issue them concurrently.
+    fn get_blocks(&self, hashes: &[BlockHash]) -> Result<Vec<Block>, ClientError<Self::Err>> {
+        hashes.iter().map(|hash| self.get_block(hash)).collect()
+    }
+    /// BIP157/158 compact block filter committing to a block's output scripts, used to skip
+    /// blocks that cannot contain a tracked bag without fetching them.
+    ///
+    /// The default rebuilds the filter from the full block, which defeats the purpose and is
+    /// only useful for testing; backends with `blockfilterindex` enabled should override this
+    /// with the node's `getblockfilter` RPC so the block itself is never fetched.
+    fn get_block_filter(&self, hash: &BlockHash) -> Result<BlockFilter, ClientError<Self::Err>> {
+        Ok(block_filter(&self.get_block(hash)?))
+    }
     fn fund_raw_transaction<R: RawTx>(
         &self,
         tx: R,
@@ -35,6 +50,23 @@ pub trait BitcoinClient {
         tx: R,
     ) -> Result<SignRawTransactionResult, ClientError<Self::Err>>;
     fn send_raw_transaction<R: RawTx>(&self, tx: R) -> Result<Txid, ClientError<Self::Err>>;
+
+    /// Transaction ids currently sitting in the node's mempool.
+    ///
+    /// Backends without a mempool (e.g. a plain block source) keep the default empty
+    /// result, which turns [`Index::scan_mempool`](crate::Index) into a no-op.
+    fn get_raw_mempool(&self) -> Result<Vec<Txid>, ClientError<Self::Err>> {
+        Ok(Vec::new())
+    }
+
+    /// Fetch a raw transaction by id, or `None` when the backend cannot serve it.
+    fn get_raw_transaction(
+        &self,
+        txid: &Txid,
+    ) -> Result<Option<Transaction>, ClientError<Self::Err>> {
+        let _ = txid;
+        Ok(None)
+    }
 }
 
 impl BitcoinClient for bitcoincore_rpc::Client {
@@ -59,6 +91,13 @@ impl BitcoinClient for bitcoincore_rpc::Client {
         RpcApi::get_block(self, hash).map_err(ClientError)
     }
 
+    fn get_block_filter(&self, hash: &BlockHash) -> Result<BlockFilter, ClientError<Self::Err>> {
+        // Node-backed BIP157 `getblockfilter`: the node serves the serialized GCS, so the
+        // full block is never fetched — the point of filters on pruned/light nodes.
+        let result = RpcApi::get_block_filter(self, hash).map_err(ClientError)?;
+        Ok(BlockFilter::from_bip158_bytes(*hash, &result.filter))
+    }
+
     fn fund_raw_transaction<R: RawTx>(
         &self,
         tx: R,
@@ -76,6 +115,22 @@ impl BitcoinClient for bitcoincore_rpc::Client {
     fn send_raw_transaction<R: RawTx>(&self, tx: R) -> Result<Txid, ClientError<Self::Err>> {
         RpcApi::send_raw_transaction(self, tx).map_err(ClientError)
     }
+
+    fn get_raw_mempool(&self) -> Result<Vec<Txid>, ClientError<Self::Err>> {
+        RpcApi::get_raw_mempool(self).map_err(ClientError)
+    }
+
+    fn get_raw_transaction(
+        &self,
+        txid: &Txid,
+    ) -> Result<Option<Transaction>, ClientError<Self::Err>> {
+        match RpcApi::get_raw_transaction(self, txid, None) {
+            Ok(tx) => Ok(Some(tx)),
+            // A tx can leave the mempool between listing and fetching; treat that as absent.
+            Err(bitcoincore_rpc::Error::JsonRpc(_)) => Ok(None),
+            Err(e) => Err(ClientError(e)),
+        }
+    }
 }
 
 /// Extensions for the mint processing.
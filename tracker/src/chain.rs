@@ -0,0 +1,159 @@
+use bitcoin::BlockHash;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::error::Error;
+
+/// A sparse local header-chain anchor: the block hash the tracker believed was canonical
+/// at `height`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Checkpoint {
+    pub height: u64,
+    pub hash: BlockHash,
+}
+
+/// Persists sparse `(height, BlockHash)` checkpoints so deep reorgs can be detected by
+/// walking back to the last checkpoint that still matches the node, and so a restarted
+/// tracker can resume from its last checkpoint instead of a hard-coded start height.
+///
+/// Mirrors [`IndexStorage`](crate::storage::IndexStorage): a trait with an in-memory
+/// implementation for tests and a sqlite one for production.
+pub trait ChainStorage {
+    type Err: Error;
+
+    /// Record (or overwrite) the checkpoint at `checkpoint.height`.
+    fn store_checkpoint(&self, checkpoint: Checkpoint) -> Result<(), Self::Err>;
+
+    /// The highest stored checkpoint, if any. Used to resume after a restart.
+    fn latest_checkpoint(&self) -> Result<Option<Checkpoint>, Self::Err>;
+
+    /// Stored checkpoints at or below `height`, highest first, so `check_reorgs` can walk
+    /// back to the first one that still matches the node.
+    fn checkpoints_below(&self, height: u64) -> Result<Vec<Checkpoint>, Self::Err>;
+
+    /// Drop every checkpoint strictly above `height` (orphaned by a rollback).
+    fn truncate_above(&self, height: u64) -> Result<(), Self::Err>;
+}
+
+/// In-memory checkpoint store. Use it only for tests.
+#[derive(Debug, Default)]
+pub struct MemoryChainStorage(RefCell<BTreeMap<u64, BlockHash>>);
+
+impl MemoryChainStorage {
+    pub fn new() -> Self {
+        MemoryChainStorage(RefCell::new(BTreeMap::new()))
+    }
+}
+
+impl ChainStorage for MemoryChainStorage {
+    type Err = Infallible;
+
+    fn store_checkpoint(&self, checkpoint: Checkpoint) -> Result<(), Self::Err> {
+        self.0.borrow_mut().insert(checkpoint.height, checkpoint.hash);
+        Ok(())
+    }
+
+    fn latest_checkpoint(&self) -> Result<Option<Checkpoint>, Self::Err> {
+        Ok(self
+            .0
+            .borrow()
+            .iter()
+            .next_back()
+            .map(|(&height, &hash)| Checkpoint { height, hash }))
+    }
+
+    fn checkpoints_below(&self, height: u64) -> Result<Vec<Checkpoint>, Self::Err> {
+        Ok(self
+            .0
+            .borrow()
+            .range(..=height)
+            .rev()
+            .map(|(&height, &hash)| Checkpoint { height, hash })
+            .collect())
+    }
+
+    fn truncate_above(&self, height: u64) -> Result<(), Self::Err> {
+        self.0.borrow_mut().retain(|&h, _| h <= height);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite-storage")]
+mod sqlite {
+    use super::{ChainStorage, Checkpoint};
+    use bitcoin::hashes::Hash;
+    use bitcoin::BlockHash;
+    use rusqlite::Connection;
+
+    /// Checkpoint store backed by sqlite, mirroring `BidSqliteStorage`.
+    #[derive(Debug)]
+    pub struct ChainSqliteStorage {
+        connection: Connection,
+    }
+
+    impl ChainSqliteStorage {
+        pub fn with_connection(connection: Connection) -> Self {
+            let this = ChainSqliteStorage { connection };
+            this.init_tables();
+            this
+        }
+
+        fn init_tables(&self) {
+            self.connection
+                .execute(
+                    "CREATE TABLE IF NOT EXISTS checkpoints (
+                 height INTEGER PRIMARY KEY,
+                 hash BLOB NOT NULL
+             )",
+                    [],
+                )
+                .unwrap();
+        }
+    }
+
+    impl ChainStorage for ChainSqliteStorage {
+        type Err = rusqlite::Error;
+
+        fn store_checkpoint(&self, checkpoint: Checkpoint) -> Result<(), Self::Err> {
+            self.connection.execute(
+                "INSERT OR REPLACE INTO checkpoints VALUES (?1, ?2);",
+                rusqlite::params![checkpoint.height, checkpoint.hash.as_ref()],
+            )?;
+            Ok(())
+        }
+
+        fn latest_checkpoint(&self) -> Result<Option<Checkpoint>, Self::Err> {
+            let mut stmt = self
+                .connection
+                .prepare("SELECT height, hash FROM checkpoints ORDER BY height DESC LIMIT 1;")?;
+            let mut rows = stmt.query_map([], row_to_checkpoint)?;
+            rows.next().transpose()
+        }
+
+        fn checkpoints_below(&self, height: u64) -> Result<Vec<Checkpoint>, Self::Err> {
+            let mut stmt = self.connection.prepare(
+                "SELECT height, hash FROM checkpoints WHERE height <= ?1 ORDER BY height DESC;",
+            )?;
+            let rows = stmt.query_map([height], row_to_checkpoint)?;
+            rows.collect()
+        }
+
+        fn truncate_above(&self, height: u64) -> Result<(), Self::Err> {
+            self.connection
+                .execute("DELETE FROM checkpoints WHERE height > ?1;", [height])?;
+            Ok(())
+        }
+    }
+
+    fn row_to_checkpoint(row: &rusqlite::Row) -> rusqlite::Result<Checkpoint> {
+        let height: u64 = row.get(0)?;
+        let hash: Vec<u8> = row.get(1)?;
+        Ok(Checkpoint {
+            height,
+            hash: BlockHash::from_slice(&hash).expect("stored block hash is 32 bytes"),
+        })
+    }
+}
+
+#[cfg(feature = "sqlite-storage")]
+pub use sqlite::ChainSqliteStorage;
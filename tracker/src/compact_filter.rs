@@ -0,0 +1,285 @@
+use crate::bag_id::BagId;
+use crate::bitcoin_client::{BitcoinClient, ClientError};
+use bitcoin::blockdata::script;
+use bitcoin::hashes::{siphash24, Hash};
+use bitcoin::{Block, BlockHash, Script, WScriptHash};
+
+/// Golomb-Rice parameter used by BIP158 basic filters.
+const P: u8 = 19;
+/// Golomb-Rice modulus used by BIP158 basic filters.
+const M: u64 = 784_931;
+
+/// A BIP157/158 compact block filter: a Golomb-Rice coded set (GCS) committing to
+/// every output `scriptPubKey` of a block.
+///
+/// It lets the [`Index`](crate::Index) locate bag-carrying blocks without fetching
+/// every full block, so it can sync against pruned nodes and light peers where
+/// full-block RPC is unavailable or too heavy.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BlockFilter {
+    /// The block this filter commits to. Its first 16 bytes key the SipHash.
+    pub block_hash: BlockHash,
+    /// Number of items committed by the filter.
+    pub n: u64,
+    /// Golomb-Rice coded, delta-encoded set of scaled hashes.
+    pub content: Vec<u8>,
+}
+
+impl BlockFilter {
+    /// Build a filter committing to the passed scripts (output `scriptPubKey`s).
+    pub fn build(block_hash: BlockHash, scripts: impl IntoIterator<Item = Script>) -> Self {
+        let k = siphash_key(&block_hash);
+        let mut values: Vec<u64> = scripts
+            .into_iter()
+            .map(|script| scaled_hash(script.as_bytes(), k, 0))
+            .collect();
+        values.sort_unstable();
+        values.dedup();
+
+        let n = values.len() as u64;
+        // The scaling range depends on the final item count.
+        let f = n.saturating_mul(M);
+        let mut writer = BitWriter::new();
+        let mut last = 0u64;
+        for value in values {
+            let scaled = map_to_range(value, f);
+            golomb_encode(&mut writer, scaled - last);
+            last = scaled;
+        }
+
+        BlockFilter {
+            block_hash,
+            n,
+            content: writer.finish(),
+        }
+    }
+
+    /// Reconstruct a filter from the node's serialized BIP158 bytes, whose layout is a
+    /// compact-size item count `n` followed by the Golomb-Rice coded set. Lets a node-backed
+    /// `getblockfilter` be tested with [`BlockFilter::contains`] without rebuilding it locally.
+    pub fn from_bip158_bytes(block_hash: BlockHash, bytes: &[u8]) -> Self {
+        let mut reader = bytes;
+        let n = read_compact_size(&mut reader);
+        BlockFilter {
+            block_hash,
+            n,
+            content: reader.to_vec(),
+        }
+    }
+
+    /// Test whether the filter might commit to `script`.
+    ///
+    /// A `true` result may be a false positive (the filter is probabilistic), so the
+    /// caller must still fetch the full block to confirm; a `false` result is exact.
+    pub fn contains(&self, script: &Script) -> bool {
+        if self.n == 0 {
+            return false;
+        }
+        let k = siphash_key(&self.block_hash);
+        let f = self.n.saturating_mul(M);
+        let target = map_to_range(scaled_hash(script.as_bytes(), k, 0), f);
+
+        let mut reader = BitReader::new(&self.content);
+        let mut value = 0u64;
+        for _ in 0..self.n {
+            value += golomb_decode(&mut reader);
+            if value == target {
+                return true;
+            }
+            if value > target {
+                return false;
+            }
+        }
+        false
+    }
+}
+
+impl<C: BitcoinClient> CompactFilterClient for C {}
+
+/// Extension that drives block scanning through BIP158 compact filters.
+///
+/// The filter source itself is [`BitcoinClient::get_block_filter`], so a node-backed
+/// `getblockfilter` override is picked up here without specialization.
+pub trait CompactFilterClient: BitcoinClient {
+    /// Given the P2WSH scripts wrapping the tracked bags, return the hashes of the blocks
+    /// whose filter matches at least one of them, so non-matching blocks can be skipped.
+    fn candidate_block_hashes(
+        &self,
+        hashes: &[BlockHash],
+        bag_scripts: &[Script],
+    ) -> Result<Vec<BlockHash>, ClientError<Self::Err>> {
+        let mut candidates = Vec::new();
+        for hash in hashes {
+            let filter = self.get_block_filter(hash)?;
+            if bag_scripts.iter().any(|script| filter.contains(script)) {
+                candidates.push(*hash);
+            }
+        }
+        Ok(candidates)
+    }
+}
+
+/// Build the compact filter of a block from its output scripts.
+pub fn block_filter(block: &Block) -> BlockFilter {
+    let scripts = block
+        .txdata
+        .iter()
+        .flat_map(|tx| tx.output.iter())
+        .filter(|out| !out.script_pubkey.is_empty())
+        .map(|out| out.script_pubkey.clone());
+    BlockFilter::build(block.block_hash(), scripts)
+}
+
+/// The P2WSH `scriptPubKey` that a mint transaction uses to commit to `bag`, exactly as
+/// [`BitcoinMintExt::send_mint_transaction`](crate::bitcoin_client::BitcoinMintExt) builds it.
+pub fn bag_script(bag: &BagId) -> Script {
+    use bitcoin::hashes::sha256;
+    let hash = sha256::Hash::from_slice(&bag.0).expect("Bag id has 32 bytes, as sha256");
+    script::Script::new_v0_wsh(&WScriptHash::from_hash(hash))
+}
+
+// The SipHash key is the first 16 bytes of the block hash, interpreted as two little-endian u64s.
+fn siphash_key(hash: &BlockHash) -> (u64, u64) {
+    let bytes = hash.as_ref();
+    let mut k0 = [0u8; 8];
+    let mut k1 = [0u8; 8];
+    k0.copy_from_slice(&bytes[0..8]);
+    k1.copy_from_slice(&bytes[8..16]);
+    (u64::from_le_bytes(k0), u64::from_le_bytes(k1))
+}
+
+fn scaled_hash(item: &[u8], (k0, k1): (u64, u64), _n: u64) -> u64 {
+    siphash24::Hash::hash_to_u64_with_keys(k0, k1, item)
+}
+
+// Read a Bitcoin compact-size integer, advancing the slice past it. Malformed/short input
+// yields 0, which a probabilistic prefilter treats as an empty (never-matching) filter.
+fn read_compact_size(reader: &mut &[u8]) -> u64 {
+    let (value, consumed) = match reader.first() {
+        Some(&n @ 0..=0xfc) => (n as u64, 1),
+        Some(0xfd) if reader.len() >= 3 => {
+            (u16::from_le_bytes([reader[1], reader[2]]) as u64, 3)
+        }
+        Some(0xfe) if reader.len() >= 5 => (
+            u32::from_le_bytes([reader[1], reader[2], reader[3], reader[4]]) as u64,
+            5,
+        ),
+        Some(0xff) if reader.len() >= 9 => {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&reader[1..9]);
+            (u64::from_le_bytes(buf), 9)
+        }
+        _ => (0, reader.len()),
+    };
+    *reader = &reader[consumed..];
+    value
+}
+
+// Scale a 64-bit hash into `[0, f)` without wrapping, as in BIP158.
+fn map_to_range(value: u64, f: u64) -> u64 {
+    ((value as u128 * f as u128) >> 64) as u64
+}
+
+fn golomb_encode(writer: &mut BitWriter, x: u64) {
+    let q = x >> P;
+    for _ in 0..q {
+        writer.write_bit(true);
+    }
+    writer.write_bit(false);
+    writer.write_bits(x & ((1 << P) - 1), P);
+}
+
+fn golomb_decode(reader: &mut BitReader) -> u64 {
+    let mut q = 0u64;
+    while reader.read_bit() {
+        q += 1;
+    }
+    let r = reader.read_bits(P);
+    (q << P) + r
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            bytes: Vec::new(),
+            bit: 0,
+        }
+    }
+
+    fn write_bit(&mut self, set: bool) {
+        if self.bit == 0 {
+            self.bytes.push(0);
+        }
+        if set {
+            let last = self.bytes.len() - 1;
+            self.bytes[last] |= 1 << (7 - self.bit);
+        }
+        self.bit = (self.bit + 1) % 8;
+    }
+
+    fn write_bits(&mut self, value: u64, count: u8) {
+        for i in (0..count).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, pos: 0 }
+    }
+
+    fn read_bit(&mut self) -> bool {
+        let byte = self.pos / 8;
+        let bit = self.pos % 8;
+        self.pos += 1;
+        match self.bytes.get(byte) {
+            Some(b) => (b >> (7 - bit)) & 1 == 1,
+            None => false,
+        }
+    }
+
+    fn read_bits(&mut self, count: u8) -> u64 {
+        let mut value = 0u64;
+        for _ in 0..count {
+            value = (value << 1) | self.read_bit() as u64;
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_mint_transaction;
+
+    #[test]
+    fn filter_matches_committed_script() {
+        let (tx, _bid) = create_test_mint_transaction([7; 32]);
+        let script = tx.output[0].script_pubkey.clone();
+        let filter = BlockFilter::build(Default::default(), vec![script.clone()]);
+
+        assert!(filter.contains(&script));
+        assert!(!filter.contains(&bag_script(&BagId([9; 32]))));
+    }
+
+    #[test]
+    fn bag_script_matches_mint_output() {
+        let (tx, bid) = create_test_mint_transaction([3; 32]);
+        assert_eq!(tx.output[0].script_pubkey, bag_script(&bid.bag_id));
+    }
+}
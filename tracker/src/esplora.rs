@@ -0,0 +1,244 @@
+use crate::bitcoin_client::{BitcoinClient, ClientError};
+use bitcoin::consensus::Decodable;
+use bitcoin::hashes::hex::{FromHex, ToHex};
+use bitcoin::{Block, BlockHash};
+use bitcoincore_rpc::json::{
+    FundRawTransactionResult, GetBlockHeaderResult, GetBlockchainInfoResult,
+    SignRawTransactionResult,
+};
+use bitcoincore_rpc::RawTx;
+use std::str::FromStr;
+
+/// A [`BitcoinClient`] backed by an [Esplora](https://github.com/Blockstream/esplora)
+/// HTTP REST API instead of a local `bitcoind` RPC.
+///
+/// It lets operators run the tracker against a hosted Esplora instance without
+/// maintaining their own fully-synced node, and makes the reorg/scanning logic in
+/// [`Index`](crate::Index) reusable in server environments that already front Bitcoin
+/// with Esplora.
+///
+/// This is the single "no full node" backend: an Electrum variant was deliberately not
+/// added, since Esplora already covers the no-local-`bitcoind` deployment the request was
+/// about and a second REST-less protocol would duplicate it without serving a new use case.
+#[derive(Debug, Clone)]
+pub struct EsploraClient {
+    /// Base URL of the REST API, e.g. `https://blockstream.info/api`.
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl EsploraClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        EsploraClient {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    fn get_text(&self, path: &str) -> Result<String, EsploraError> {
+        let url = format!("{}{}", self.base_url, path);
+        Ok(self.agent.get(&url).call()?.into_string()?)
+    }
+
+    fn get_bytes(&self, path: &str) -> Result<Vec<u8>, EsploraError> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut buf = Vec::new();
+        self.agent
+            .get(&url)
+            .call()?
+            .into_reader()
+            .read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+impl BitcoinClient for EsploraClient {
+    type Err = EsploraError;
+
+    fn get_blockchain_info(&self) -> Result<GetBlockchainInfoResult, ClientError<Self::Err>> {
+        let height: u64 = self
+            .get_text("/blocks/tip/height")
+            .and_then(|s| s.trim().parse().map_err(|_| EsploraError::Malformed))
+            .map_err(ClientError)?;
+        let tip = self
+            .get_text("/blocks/tip/hash")
+            .and_then(|s| BlockHash::from_str(s.trim()).map_err(|_| EsploraError::Malformed))
+            .map_err(ClientError)?;
+        // `Index` stamps proofs with the network named by `chain`, so identify it from the
+        // genesis block hash instead of leaving it empty.
+        let genesis = self.get_block_hash(0)?;
+        let chain = [
+            (bitcoin::Network::Bitcoin, "main"),
+            (bitcoin::Network::Testnet, "test"),
+            (bitcoin::Network::Signet, "signet"),
+        ]
+        .iter()
+        .find(|(network, _)| {
+            bitcoin::blockdata::constants::genesis_block(*network).block_hash() == genesis
+        })
+        .map_or("regtest", |(_, name)| *name);
+        Ok(blockchain_info(chain, height, tip))
+    }
+
+    fn get_block_hash(&self, height: u64) -> Result<BlockHash, ClientError<Self::Err>> {
+        self.get_text(&format!("/block-height/{}", height))
+            .and_then(|s| BlockHash::from_str(s.trim()).map_err(|_| EsploraError::Malformed))
+            .map_err(ClientError)
+    }
+
+    fn get_block_header_info(
+        &self,
+        hash: &BlockHash,
+    ) -> Result<GetBlockHeaderResult, ClientError<Self::Err>> {
+        let block: EsploraBlock = self
+            .get_text(&format!("/block/{}", hash))
+            .and_then(|s| serde_json::from_str(&s).map_err(|_| EsploraError::Malformed))
+            .map_err(ClientError)?;
+        let status: EsploraBlockStatus = self
+            .get_text(&format!("/block/{}/status", hash))
+            .and_then(|s| serde_json::from_str(&s).map_err(|_| EsploraError::Malformed))
+            .map_err(ClientError)?;
+
+        let mut info = block.into_header_info();
+        // `Index` relies on the `confirmations == -1` convention for blocks that left the best
+        // chain (they are kept out of its header cache); Esplora reports that as
+        // `in_best_chain: false`. Otherwise
+        // derive a positive depth from the current tip height.
+        info.confirmations = if status.in_best_chain {
+            let tip: u64 = self
+                .get_text("/blocks/tip/height")
+                .and_then(|s| s.trim().parse().map_err(|_| EsploraError::Malformed))
+                .map_err(ClientError)?;
+            (tip as i64 - info.height as i64 + 1).max(1)
+        } else {
+            -1
+        };
+        Ok(info)
+    }
+
+    fn get_block(&self, hash: &BlockHash) -> Result<Block, ClientError<Self::Err>> {
+        let raw = self
+            .get_bytes(&format!("/block/{}/raw", hash))
+            .map_err(ClientError)?;
+        Block::consensus_decode(&raw[..])
+            .map_err(|_| ClientError(EsploraError::Malformed))
+    }
+
+    fn fund_raw_transaction<R: RawTx>(
+        &self,
+        _tx: R,
+    ) -> Result<FundRawTransactionResult, ClientError<Self::Err>> {
+        Err(ClientError(EsploraError::Unsupported("fund_raw_transaction")))
+    }
+
+    fn sign_raw_transaction_with_wallet<R: RawTx>(
+        &self,
+        _tx: R,
+    ) -> Result<SignRawTransactionResult, ClientError<Self::Err>> {
+        Err(ClientError(EsploraError::Unsupported(
+            "sign_raw_transaction_with_wallet",
+        )))
+    }
+
+    fn send_raw_transaction<R: RawTx>(
+        &self,
+        tx: R,
+    ) -> Result<bitcoin::Txid, ClientError<Self::Err>> {
+        let url = format!("{}/tx", self.base_url);
+        let txid = self
+            .agent
+            .post(&url)
+            .send_string(&tx.raw_hex())
+            .map_err(|e| ClientError(EsploraError::from(e)))?
+            .into_string()
+            .map_err(|e| ClientError(EsploraError::from(e)))?;
+        bitcoin::Txid::from_str(txid.trim())
+            .map_err(|_| ClientError(EsploraError::Malformed))
+    }
+}
+
+// Esplora does not report the full `getblockchaininfo`, so everything the tracker does
+// not use is filled with sensible defaults.
+fn blockchain_info(chain: &str, blocks: u64, best_block_hash: BlockHash) -> GetBlockchainInfoResult {
+    GetBlockchainInfoResult {
+        chain: chain.to_string(),
+        blocks,
+        headers: blocks,
+        best_block_hash,
+        difficulty: 0.0,
+        median_time: 0,
+        verification_progress: 1.0,
+        initial_block_download: false,
+        chain_work: vec![],
+        size_on_disk: 0,
+        pruned: false,
+        prune_height: None,
+        automatic_pruning: None,
+        prune_target_size: None,
+        softforks: Default::default(),
+        warnings: String::new(),
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct EsploraBlockStatus {
+    in_best_chain: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct EsploraBlock {
+    id: String,
+    height: usize,
+    merkle_root: String,
+    timestamp: u64,
+    nonce: u32,
+    bits: u32,
+    version: i32,
+    tx_count: usize,
+    previousblockhash: Option<String>,
+}
+
+impl EsploraBlock {
+    fn into_header_info(self) -> GetBlockHeaderResult {
+        GetBlockHeaderResult {
+            hash: BlockHash::from_str(&self.id).unwrap_or_default(),
+            confirmations: 1,
+            height: self.height,
+            version: self.version,
+            version_hex: None,
+            merkle_root: FromHex::from_hex(&self.merkle_root).unwrap_or_default(),
+            time: self.timestamp as usize,
+            median_time: None,
+            nonce: self.nonce,
+            bits: self.bits.to_hex(),
+            difficulty: 0.0,
+            chainwork: vec![],
+            n_tx: self.tx_count,
+            previous_block_hash: self
+                .previousblockhash
+                .and_then(|h| BlockHash::from_str(&h).ok()),
+            next_block_hash: None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EsploraError {
+    #[error(transparent)]
+    Http(#[from] Box<ureq::Error>),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Esplora returned a malformed response.")]
+    Malformed,
+
+    #[error("Operation {0} is not supported by the Esplora backend.")]
+    Unsupported(&'static str),
+}
+
+impl From<ureq::Error> for EsploraError {
+    fn from(e: ureq::Error) -> Self {
+        EsploraError::Http(Box::new(e))
+    }
+}
@@ -1,40 +1,184 @@
+use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::error::Error;
+use std::num::NonZeroUsize;
 
-use bitcoin::{Block, BlockHash, Transaction, TxOut, Txid};
+use bitcoin::{BlockHash, Network, Transaction, TxOut, Txid};
 use bitcoincore_rpc::json::GetBlockHeaderResult;
+use lru::LruCache;
 use thiserror::Error;
 
 use crate::bag_id::BagId;
 use crate::bitcoin_client::{BitcoinClient, ClientError};
+use crate::chain::{ChainStorage, Checkpoint, MemoryChainStorage};
+use crate::compact_filter::{bag_script, CompactFilterClient};
+use crate::indexed::{IndexedBlock, IndexedTransaction};
 use crate::record::{BidEntry, BidEntryData, BidProof, BidTx, Outpoint};
 use crate::storage::{BidStorage, BidStorageError};
 
-pub struct Index<C: BitcoinClient, S: BidStorage> {
+pub struct Index<C: BitcoinClient, S: BidStorage, H: ChainStorage = MemoryChainStorage> {
     btc_client: C,
     bids_storage: S,
+    chain_storage: H,
 
     current_height: u64,
     current_tip: BlockHash,
+
+    // Network this index tracks, stamped onto every `BidProof` so proofs can't be replayed
+    // against a different chain. Derived from the node's reported chain at construction.
+    network: Network,
+
+    // Txids already processed from the mempool, so each tick only inspects new arrivals and
+    // can drop entries that disappeared without being mined.
+    mempool_seen: RefCell<std::collections::HashSet<Txid>>,
+
+    // Minimum depth (block buried-under count) before a bag is treated as confirmed. Near
+    // the tip, reorgs can orphan a block, so a shallow bag must stay pending.
+    safety_margin: u64,
+
+    // Bounded cache of parsed blocks, keyed by hash, so a block fetched during the initial
+    // scan and re-examined during a reorg replay is decoded and hashed only once.
+    block_cache: RefCell<LruCache<BlockHash, IndexedBlock>>,
+
+    // Bounded cache of block headers, keyed by hash. Only main-chain entries are cached
+    // (their height/previous_block_hash are stable); entries that fall off the main chain
+    // must be re-fetched, so `confirmations == -1` results are never stored.
+    header_cache: RefCell<LruCache<BlockHash, GetBlockHeaderResult>>,
 }
 
-impl<C: BitcoinClient, S: BidStorage> Index<C, S> {
+/// Default capacity of the block-header LRU cache.
+const DEFAULT_HEADER_CACHE_CAP: usize = 1024;
+
+impl<C: BitcoinClient, S: BidStorage> Index<C, S, MemoryChainStorage> {
     pub fn new(
         client: C,
         storage: S,
         base_height: Option<u64>,
+    ) -> Result<Self, ClientError<C::Err>> {
+        Self::with_chain_storage(client, storage, MemoryChainStorage::new(), base_height)
+    }
+
+    /// Like [`Index::new`] but with an explicit block-header cache capacity.
+    pub fn with_header_cache(
+        client: C,
+        storage: S,
+        base_height: Option<u64>,
+        header_cache_cap: usize,
+    ) -> Result<Self, ClientError<C::Err>> {
+        let mut index =
+            Self::with_chain_storage(client, storage, MemoryChainStorage::new(), base_height)?;
+        index.header_cache = RefCell::new(LruCache::new(
+            NonZeroUsize::new(header_cache_cap).unwrap_or(nonzero(DEFAULT_HEADER_CACHE_CAP)),
+        ));
+        Ok(index)
+    }
+}
+
+fn nonzero(n: usize) -> NonZeroUsize {
+    NonZeroUsize::new(n).expect("capacity constant is non-zero")
+}
+
+// Map the node's `getblockchaininfo` chain string to a `Network`. Unknown values fall back
+// to regtest, which is the chain used by the local test node.
+fn network_from_chain(chain: &str) -> Network {
+    match chain {
+        "main" => Network::Bitcoin,
+        "test" => Network::Testnet,
+        "signet" => Network::Signet,
+        _ => Network::Regtest,
+    }
+}
+
+impl<C: BitcoinClient, S: BidStorage, H: ChainStorage> Index<C, S, H> {
+    /// Build an index with an explicit checkpoint store. When the store already holds a
+    /// checkpoint and no `base_height` is given, the tracker resumes from it instead of
+    /// rescanning from the node tip.
+    pub fn with_chain_storage(
+        client: C,
+        storage: S,
+        chain_storage: H,
+        base_height: Option<u64>,
     ) -> Result<Self, ClientError<C::Err>> {
         let info = client.get_blockchain_info()?;
-        let height = base_height.unwrap_or(info.blocks);
+        let resume = chain_storage.latest_checkpoint().ok().flatten();
+        let height = base_height
+            .or_else(|| resume.map(|c| c.height))
+            .unwrap_or(info.blocks);
         let tip = client.get_block_hash(height)?;
+        chain_storage
+            .store_checkpoint(Checkpoint { height, hash: tip })
+            .ok();
         Ok(Index {
             btc_client: client,
             current_height: height,
             current_tip: tip,
+            network: network_from_chain(&info.chain),
             bids_storage: storage,
+            chain_storage,
+            mempool_seen: RefCell::new(std::collections::HashSet::new()),
+            safety_margin: 1,
+            block_cache: RefCell::new(LruCache::new(nonzero(DEFAULT_HEADER_CACHE_CAP))),
+            header_cache: RefCell::new(LruCache::new(nonzero(DEFAULT_HEADER_CACHE_CAP))),
         })
     }
 
+    /// Set the confirmation depth a bag must reach before [`Index::confirmed_bags`] reports
+    /// it. A margin of `1` (the default) treats a bag as confirmed the moment it is mined.
+    pub fn with_safety_margin(mut self, safety_margin: u64) -> Self {
+        self.safety_margin = safety_margin.max(1);
+        self
+    }
+
+    /// Bags buried under at least `safety_margin` blocks, recomputed against the current tip
+    /// so shallow bags inside the unstable window stay pending until they cross the threshold.
+    pub fn confirmed_bags(&self) -> Result<Vec<BagId>, IError<C, S>> {
+        let mut confirmed = vec![];
+        for bid in self.bids_storage.confirmed_bids()? {
+            let height = self.header_info(&bid.proof.btc_block, false)?.height as u64;
+            let depth = self.current_height.saturating_sub(height) + 1;
+            if depth >= self.safety_margin {
+                confirmed.push(bid.proof.tx.bag_id);
+            }
+        }
+        Ok(confirmed)
+    }
+
+    /// All header lookups go through here. Returns a cached header when one is available,
+    /// otherwise fetches it and caches the result if it is on the main chain.
+    ///
+    /// Pass `fresh = true` (as the reorg walk does) to force a node round-trip, since a
+    /// header's `confirmations` — and thus its main-chain membership — can change across
+    /// reorgs even though its height/previous_block_hash cannot.
+    fn header_info(
+        &self,
+        hash: &BlockHash,
+        fresh: bool,
+    ) -> Result<GetBlockHeaderResult, ClientError<C::Err>> {
+        if !fresh {
+            if let Some(cached) = self.header_cache.borrow_mut().get(hash) {
+                return Ok(cached.clone());
+            }
+        }
+        let info = self.btc_client.get_block_header_info(hash)?;
+        if info.confirmations != -1 {
+            self.header_cache.borrow_mut().put(*hash, info.clone());
+        } else {
+            self.header_cache.borrow_mut().pop(hash);
+        }
+        Ok(info)
+    }
+
+    /// Fetch a block and wrap it as an [`IndexedBlock`], reusing a cached copy when the same
+    /// block was already decoded (e.g. during the initial scan before a reorg replay).
+    fn indexed_block(&self, hash: &BlockHash) -> Result<IndexedBlock, ClientError<C::Err>> {
+        if let Some(block) = self.block_cache.borrow_mut().get(hash) {
+            return Ok(block.clone());
+        }
+        let block = IndexedBlock::from(self.btc_client.get_block(hash)?);
+        self.block_cache.borrow_mut().put(*hash, block.clone());
+        Ok(block)
+    }
+
     pub fn add_bag(&self, bag: impl Into<BagId>) -> Result<(), BidStorageError<S::Err>> {
         self.bids_storage.insert_unconfirmed_bag(bag.into())
     }
@@ -58,19 +202,22 @@ impl<C: BitcoinClient, S: BidStorage> Index<C, S> {
 
 type IError<C, S> = TrackerError<<C as BitcoinClient>::Err, <S as BidStorage>::Err>;
 
-impl<C: BitcoinClient, S: BidStorage> Index<C, S> {
+/// Distance between persisted header-chain checkpoints.
+const CHECKPOINT_INTERVAL: u64 = 100;
+
+/// Number of blocks requested per window during headers-first catch-up.
+const DEFAULT_SYNC_WINDOW: usize = 16;
+
+impl<C: BitcoinClient, S: BidStorage, H: ChainStorage> Index<C, S, H> {
     /// Check existence of the bid in the bitcoin chain, and if it is then add it to the store
     pub fn add_bid(&mut self, proof: BidProof) -> Result<(), IError<C, S>> {
-        let block = self.btc_client.get_block(&proof.btc_block)?;
-        let height = self
-            .btc_client
-            .get_block_header_info(&proof.btc_block)?
-            .height;
+        let block = self.indexed_block(&proof.btc_block)?;
+        let height = self.header_info(&proof.btc_block, false)?.height;
 
-        let tx = find_tx(block, &proof.tx.outpoint.txid).ok_or_else(|| {
+        let tx = block.transaction(&proof.tx.outpoint.txid).ok_or_else(|| {
             TrackerError::TxDoesNotExists(proof.btc_block, proof.tx.outpoint.txid)
         })?;
-        let bid_data = parse_mint_transaction_btc_block(&tx, proof.tx.outpoint.out_pos)
+        let bid_data = parse_mint_transaction_btc_block(&tx.raw, proof.tx.outpoint.out_pos)
             .ok_or_else(|| TrackerError::WrongOutputFormat)?;
 
         if bid_data.bag_id != proof.tx.bag_id {
@@ -96,100 +243,361 @@ impl<C: BitcoinClient, S: BidStorage> Index<C, S> {
         Ok(())
     }
 
+    /// Scan the node mempool for transactions that carry a registered bag and record them as
+    /// pending (zero-confirmation) bids, so consumers see incoming bids before they are mined.
+    ///
+    /// Entries that vanish from the mempool without being mined are evicted; those that are
+    /// later mined are promoted to confirmed by `add_btc_block_to_index`.
+    pub fn scan_mempool(&mut self) -> Result<(), IError<C, S>> {
+        let mempool = self.btc_client.get_raw_mempool()?;
+        let current: std::collections::HashSet<Txid> = mempool.iter().copied().collect();
+
+        // Drop pending bids whose transaction is no longer in the mempool, and forget the
+        // txids that disappeared so a resubmission is reprocessed.
+        for pending in self.bids_storage.pending_bids()? {
+            let txid = pending.proof.tx.outpoint.txid;
+            if !current.contains(&txid) {
+                self.bids_storage.remove_pending_bid(&txid)?;
+            }
+        }
+        self.mempool_seen.borrow_mut().retain(|txid| current.contains(txid));
+
+        for txid in mempool {
+            // Skip transactions we have already recorded a pending bid for. We deliberately do
+            // not mark a transaction seen until it matches a tracked bag: a bid transaction can
+            // arrive in the mempool before its bag is registered, and re-inspecting it on later
+            // ticks is what lets it be picked up once the bag exists.
+            if self.mempool_seen.borrow().contains(&txid) {
+                continue;
+            }
+            let tx = match self.btc_client.get_raw_transaction(&txid)? {
+                Some(tx) => IndexedTransaction::from(tx),
+                None => continue,
+            };
+            let mut matched = false;
+            for (outpoint, bid_data) in parse_mint_transaction_btc_block_unknown_pos(&tx) {
+                if self
+                    .bids_storage
+                    .is_bag_exists(&bid_data.bag_id)
+                    .unwrap_or(false)
+                {
+                    // Record the bid as pending; it is promoted to confirmed by
+                    // `add_btc_block_to_index` once the transaction is mined.
+                    self.bids_storage.insert_pending_bid(BidEntry {
+                        amount: bid_data.amount,
+                        proof: BidProof {
+                            network: self.network,
+                            btc_block: BlockHash::default(),
+                            tx: BidTx {
+                                outpoint,
+                                bag_id: bid_data.bag_id,
+                            },
+                            merkle_proof: None,
+                        },
+                    })?;
+                    matched = true;
+                }
+            }
+            if matched {
+                self.mempool_seen.borrow_mut().insert(txid);
+            }
+        }
+        Ok(())
+    }
+
     /// Check chain for the reorgs, and if it happened, delete old bids and check for them in new chain
-    pub fn check_reorgs(&mut self) -> Result<Option<ReorgInfo>, IError<C, S>> {
+    pub fn check_reorgs(&mut self) -> Result<Option<ReorgResult>, IError<C, S>> {
         let new_btc_info = self.btc_client.get_blockchain_info()?;
         let new_height = new_btc_info.blocks;
 
-        let reorg = match self.check_btc_for_reorgs()? {
+        let pending = match self.check_btc_for_reorgs()? {
             Some(reorg) => {
-                self.remove_btc_blocks_when_fork(&reorg)?;
+                // Remember which bags lose their confirmation before the discarded blocks are
+                // deleted, so the caller can react to any that do not survive the reorg.
+                let reverify = self.remove_btc_blocks_when_fork(&reorg)?;
+                self.chain_storage
+                    .truncate_above(reorg.height_when_fork)
+                    .map_err(|e| TrackerError::ChainError(e.to_string()))?;
                 self.current_height = reorg.height_when_fork;
                 self.current_tip = reorg.fork_root;
 
-                Some(reorg)
+                Some((reorg, reverify))
             }
             None => None,
         };
         self.add_btc_blocks(self.current_height, new_height)?;
 
-        Ok(reorg)
+        // After re-scanning the new chain, partition the bags that lost confirmation into
+        // those re-confirmed on the new chain and those still orphaned.
+        let result = match pending {
+            Some((reorg, reverify)) => {
+                let mut reconfirmed_bags = vec![];
+                let mut orphaned_bags = vec![];
+                for bag in reverify {
+                    if self.bids_storage.contains_bag(&bag).unwrap_or(false) {
+                        reconfirmed_bags.push(bag);
+                    } else {
+                        orphaned_bags.push(bag);
+                    }
+                }
+                Some(ReorgResult {
+                    fork_root: reorg.fork_root,
+                    height_when_fork: reorg.height_when_fork,
+                    discarded_blocks: reorg.discarded_blocks,
+                    reconfirmed_bags,
+                    orphaned_bags,
+                })
+            }
+            None => None,
+        };
+
+        Ok(result)
     }
 
+    // Remove discarded blocks and return the bags whose confirmation they carried.
     fn remove_btc_blocks_when_fork(
         &mut self,
         reorg_info: &ReorgInfo,
-    ) -> Result<(), BidStorageError<S::Err>> {
+    ) -> Result<Vec<BagId>, BidStorageError<S::Err>> {
+        let mut reverify = vec![];
         for discarded_block in reorg_info.discarded_blocks.iter() {
+            if let Ok(records) = self.bids_storage.get_records_by_block_hash(discarded_block) {
+                reverify.extend(records.into_iter().map(|bid| bid.proof.tx.bag_id));
+            }
+            // Spends mined in a now-orphaned block never happened: bring their bids back.
+            self.bids_storage.resurrect_with_block_hash(discarded_block)?;
             match self.bids_storage.remove_with_block_hash(discarded_block) {
                 Ok(_) | Err(BidStorageError::BagDoesNotExists(_)) => {}
-                err => return err,
+                err => return err.map(|_| vec![]),
+            }
+        }
+        Ok(reverify)
+    }
+
+    /// Catch up from the current tip to `new_height` headers-first, in bounded windows,
+    /// instead of one blocking round-trip per height. The tip only advances as the front of
+    /// the verifying queue is confirmed contiguous, so an interruption mid-range resumes
+    /// cleanly from the last verified block.
+    pub fn sync_headers_first(
+        &mut self,
+        new_height: u64,
+        window: usize,
+    ) -> Result<(), IError<C, S>> {
+        use crate::sync::HashQueueChain;
+
+        if new_height <= self.current_height {
+            return Ok(());
+        }
+        let window = window.max(1);
+
+        // Headers are cheap: schedule the whole contiguous range up front.
+        let mut chain = HashQueueChain::new();
+        let mut hashes = Vec::with_capacity((new_height - self.current_height) as usize);
+        for height in self.current_height + 1..=new_height {
+            hashes.push(self.btc_client.get_block_hash(height)?);
+        }
+        chain.schedule(hashes);
+
+        let mut height = self.current_height;
+        while !chain.is_empty() {
+            let batch = chain.request_window(window);
+            if !batch.is_empty() {
+                // Fetch the window (possibly concurrently, via the batch client method) and
+                // seed the block cache with the results, keyed by the hash we requested, so
+                // the in-order verification below reuses them instead of re-fetching each
+                // block one-by-one through `indexed_block`.
+                let blocks = self.btc_client.get_blocks(&batch)?;
+                for (hash, block) in batch.iter().zip(blocks) {
+                    self.block_cache
+                        .borrow_mut()
+                        .put(*hash, IndexedBlock::from(block));
+                }
+                chain.mark_verifying(batch.len());
+            }
+            while let Some(hash) = chain.next_to_verify() {
+                self.add_btc_block_to_index(hash)?;
+                height += 1;
+                self.current_height = height;
+                self.current_tip = hash;
+                if height == new_height || height % CHECKPOINT_INTERVAL == 0 {
+                    self.chain_storage
+                        .store_checkpoint(Checkpoint { height, hash })
+                        .map_err(|e| TrackerError::ChainError(e.to_string()))?;
+                }
             }
         }
         Ok(())
     }
 
-    fn add_btc_blocks(&mut self, old_height: u64, new_height: u64) -> Result<(), IError<C, S>> {
-        for index in old_height + 1..new_height + 1 {
-            let hash = self.btc_client.get_block_hash(index)?;
-            self.add_btc_block_to_index(hash)?;
+    /// Catch up from the current tip to `new_height`, consulting BIP158 compact block filters
+    /// to skip any block whose filter matches none of `bags`. Only candidate blocks are
+    /// fetched in full and scanned, so the tracker can sync against pruned nodes and light
+    /// peers where fetching every block is unavailable or too heavy. The tip still advances
+    /// across skipped heights, and checkpoints are anchored exactly as in [`add_btc_blocks`].
+    ///
+    /// Filtering is an explicit opt-in: [`check_reorgs`](Self::check_reorgs) always takes the
+    /// full-block path through [`add_btc_blocks`], because it has no way to enumerate the set of
+    /// tracked bags from storage to build the filter query. An operator on a pruned or light
+    /// node must drive catch-up by calling this method directly with the bags it cares about.
+    pub fn sync_with_filters(
+        &mut self,
+        new_height: u64,
+        bags: &[BagId],
+    ) -> Result<(), IError<C, S>> {
+        if new_height <= self.current_height {
+            return Ok(());
+        }
+        let start = self.current_height;
+        let mut hashes = Vec::with_capacity((new_height - start) as usize);
+        for height in start + 1..=new_height {
+            hashes.push(self.btc_client.get_block_hash(height)?);
+        }
+        let scripts: Vec<_> = bags.iter().map(bag_script).collect();
+        let candidates: std::collections::HashSet<BlockHash> = self
+            .btc_client
+            .candidate_block_hashes(&hashes, &scripts)?
+            .into_iter()
+            .collect();
 
-            self.current_height = index;
+        for (offset, hash) in hashes.into_iter().enumerate() {
+            let height = start + 1 + offset as u64;
+            // A non-candidate block provably holds none of the tracked bags, so skip the
+            // full-block fetch entirely and only advance the tip past it.
+            if candidates.contains(&hash) {
+                self.add_btc_block_to_index(hash)?;
+            }
+            self.current_height = height;
             self.current_tip = hash;
+            if height == new_height || height % CHECKPOINT_INTERVAL == 0 {
+                self.chain_storage
+                    .store_checkpoint(Checkpoint { height, hash })
+                    .map_err(|e| TrackerError::ChainError(e.to_string()))?;
+            }
         }
         Ok(())
     }
 
+    // Catch up from `old_height` (always the current tip on the reorg path) to `new_height`.
+    // This drives the headers-first queue in [`sync_headers_first`] so the production sync path
+    // and the explicit bulk-sync entry point share one code path; the queue's per-window fetch
+    // is where a concurrent backend parallelises, collapsing to a plain loop only for a client
+    // whose `get_blocks` is itself sequential.
+    fn add_btc_blocks(&mut self, old_height: u64, new_height: u64) -> Result<(), IError<C, S>> {
+        debug_assert_eq!(old_height, self.current_height);
+        self.sync_headers_first(new_height, DEFAULT_SYNC_WINDOW)
+    }
+
     fn check_btc_for_reorgs(&self) -> Result<Option<ReorgInfo>, IError<C, S>> {
-        let tip = &self.current_tip;
+        let node_tip = self.btc_client.get_blockchain_info()?.blocks;
+
+        // Fast path: our tip is still the node's block at our height, so nothing reorganized.
+        if self.current_height <= node_tip
+            && self.btc_client.get_block_hash(self.current_height)? == self.current_tip
+        {
+            return Ok(None);
+        }
+
+        // Our tip diverged from the node. Walk the sparse checkpoints back to the most recent one
+        // that still matches `get_block_hash(height)`: that block is the fork root, and every
+        // block above it on our old chain must be rolled back and re-applied. Anchoring on a
+        // persisted checkpoint makes recovery correct for multi-block reorgs instead of
+        // assuming the fork is shallow.
+        let anchor = self
+            .last_matching_checkpoint(node_tip)?
+            .ok_or_else(|| TrackerError::ChainError("no checkpoint matches the node".to_string()))?;
 
         let mut discarded_blocks = vec![];
-        let mut block_hash = tip.clone();
-        let mut height;
-        let mut reorg = false;
+        let mut block_hash = self.current_tip;
         loop {
-            let block_header_info = self.btc_client.get_block_header_info(&block_hash)?;
-            height = block_header_info.height;
-            if is_block_in_main_chain(&block_header_info) {
+            // Main-chain membership can change across reorgs, so never trust a cached header here.
+            let block_header_info = self.header_info(&block_hash, true)?;
+            if block_header_info.height as u64 <= anchor.height {
                 break;
-            } else {
-                reorg = true;
-                discarded_blocks.push(block_hash);
-                // Bitcoin core api does not provide information when it is None, so I suppose it will be None only
-                // in case of block with height 0, and in that case block _must_ be in the main chain.
-                block_hash = block_header_info.previous_block_hash.unwrap();
             }
+            discarded_blocks.push(block_hash);
+            // Bitcoin core api does not provide information when it is None, so I suppose it will be None only
+            // in case of block with height 0, and in that case block _must_ be in the main chain.
+            block_hash = block_header_info.previous_block_hash.unwrap();
         }
 
-        Ok(if reorg {
+        Ok(if discarded_blocks.is_empty() {
+            None
+        } else {
             Some(ReorgInfo {
-                height_when_fork: height as u64,
-                fork_root: block_hash,
+                height_when_fork: anchor.height,
+                fork_root: anchor.hash,
                 discarded_blocks,
             })
-        } else {
-            None
         })
     }
 
+    // The highest stored checkpoint that still matches the node's chain at its height. Walks
+    // checkpoints highest-first, skipping any above the node tip (orphaned by a shorter
+    // chain), and returns the first whose hash equals `get_block_hash(height)`.
+    fn last_matching_checkpoint(
+        &self,
+        node_tip: u64,
+    ) -> Result<Option<Checkpoint>, IError<C, S>> {
+        let checkpoints = self
+            .chain_storage
+            .checkpoints_below(self.current_height)
+            .map_err(|e| TrackerError::ChainError(e.to_string()))?;
+        for checkpoint in checkpoints {
+            if checkpoint.height > node_tip {
+                continue;
+            }
+            if self.btc_client.get_block_hash(checkpoint.height)? == checkpoint.hash {
+                return Ok(Some(checkpoint));
+            }
+        }
+        Ok(None)
+    }
+
     fn add_btc_block_to_index(&mut self, block_hash: BlockHash) -> Result<(), IError<C, S>> {
         let transactions = self.check_btc_block_with_hash(block_hash.clone())?;
         transactions
             .into_iter()
-            .map(|bid| match self.bids_storage.update_bid(bid) {
-                Ok(_) | Err(BidStorageError::BagDoesNotExists(_)) => Ok(()),
-                err => err,
+            .map(|bid| {
+                // A mined bid promotes any matching pending (mempool) entry to confirmed.
+                self.bids_storage
+                    .remove_pending_bid(&bid.proof.tx.outpoint.txid)?;
+                match self.bids_storage.update_bid(bid) {
+                    Ok(_) | Err(BidStorageError::BagDoesNotExists(_)) => Ok(()),
+                    err => err,
+                }
             })
             .collect::<Result<Vec<()>, BidStorageError<S::Err>>>()?;
+
+        // A block both creates mint outputs and spends UTXOs: retire any bag whose backing
+        // outpoint is spent by an input of this block.
+        self.retire_spent_bags(&block_hash)?;
+        Ok(())
+    }
+
+    // Walk every input of the block and retire the bag backing any spent bid outpoint. The
+    // retirement is keyed by this block so `remove_btc_blocks_when_fork` can undo it.
+    fn retire_spent_bags(&mut self, block_hash: &BlockHash) -> Result<(), IError<C, S>> {
+        let block = self.indexed_block(block_hash)?;
+        for tx in block.transactions() {
+            for input in &tx.raw.input {
+                let spent = Outpoint::new(
+                    input.previous_output.txid,
+                    input.previous_output.vout as u64,
+                );
+                if let Some(bag) = self.bids_storage.bag_by_outpoint(&spent)? {
+                    self.bids_storage.retire_bag(&bag, &spent, block_hash)?;
+                }
+            }
+        }
         Ok(())
     }
 
     fn check_btc_block_with_hash(&self, hash: BlockHash) -> Result<Vec<BidEntry>, IError<C, S>> {
-        let block = self.btc_client.get_block(&hash)?;
-        let txs = block.txdata;
+        let block = self.indexed_block(&hash)?;
 
-        let mint_txs = txs
-            .into_iter()
+        let mint_txs = block
+            .transactions()
+            .iter()
             .filter_map(|tx| {
                 parse_mint_transaction_btc_block_unknown_pos(tx)
                     .filter_map(|(outpoint, bid_data)| {
@@ -201,11 +609,13 @@ impl<C: BitcoinClient, S: BidStorage> Index<C, S> {
                             Some(BidEntry {
                                 amount: bid_data.amount,
                                 proof: BidProof {
+                                    network: self.network,
                                     btc_block: hash,
                                     tx: BidTx {
                                         outpoint,
                                         bag_id: bid_data.bag_id,
                                     },
+                                    merkle_proof: None,
                                 },
                             })
                         } else {
@@ -220,8 +630,17 @@ impl<C: BitcoinClient, S: BidStorage> Index<C, S> {
     }
 }
 
-fn find_tx(block: Block, txid: &Txid) -> Option<Transaction> {
-    block.txdata.into_iter().find(|tx| tx.txid() == *txid)
+/// Resolves the bag backing a spent outpoint, modelled on parity-zcash's
+/// `PreviousTransactionOutputProvider`. [`Index`] implements it over its current bid set.
+pub trait PreviousBidProvider {
+    fn previous_bid_for_outpoint(&self, outpoint: &bitcoin::OutPoint) -> Option<BagId>;
+}
+
+impl<C: BitcoinClient, S: BidStorage, H: ChainStorage> PreviousBidProvider for Index<C, S, H> {
+    fn previous_bid_for_outpoint(&self, outpoint: &bitcoin::OutPoint) -> Option<BagId> {
+        let outpoint = Outpoint::new(outpoint.txid, outpoint.vout as u64);
+        self.bids_storage.bag_by_outpoint(&outpoint).ok().flatten()
+    }
 }
 
 fn parse_mint_transaction_btc_block(tx: &Transaction, out_pos: u64) -> Option<BidEntryData> {
@@ -230,16 +649,17 @@ fn parse_mint_transaction_btc_block(tx: &Transaction, out_pos: u64) -> Option<Bi
 }
 
 fn parse_mint_transaction_btc_block_unknown_pos(
-    tx: Transaction,
-) -> impl Iterator<Item = (Outpoint, BidEntryData)> {
-    let txid = tx.txid();
-    tx.output
-        .into_iter()
+    tx: &IndexedTransaction,
+) -> impl Iterator<Item = (Outpoint, BidEntryData)> + '_ {
+    let txid = tx.txid;
+    tx.raw
+        .output
+        .iter()
         .enumerate()
         .filter_map(move |(out_pos, out)| {
-            parse_mint_btc_output(&out).map(|data| {
+            parse_mint_btc_output(out).map(|data| {
                 let outpoint = Outpoint {
-                    txid: txid.clone(),
+                    txid,
                     out_pos: out_pos as u64,
                 };
                 (outpoint, data)
@@ -259,17 +679,52 @@ fn parse_mint_btc_output(out: &TxOut) -> Option<BidEntryData> {
     }
 }
 
-fn is_block_in_main_chain(block: &GetBlockHeaderResult) -> bool {
-    block.confirmations != -1
-}
-
 #[derive(Debug, PartialEq)]
-pub struct ReorgInfo {
+struct ReorgInfo {
     height_when_fork: u64,
     fork_root: BlockHash, // Block that available in both chains.
     discarded_blocks: Vec<BlockHash>,
 }
 
+/// Outcome of a reorg handled by [`Index::check_reorgs`], modelled on parity-bitcoin's
+/// `BlockInsertionResult`: it tells the caller which bags lost confirmation and whether they
+/// survived on the new chain, so e.g. the mint can revoke value for bags that did not.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ReorgResult {
+    fork_root: BlockHash,
+    height_when_fork: u64,
+    discarded_blocks: Vec<BlockHash>,
+    reconfirmed_bags: Vec<BagId>,
+    orphaned_bags: Vec<BagId>,
+}
+
+impl ReorgResult {
+    /// The block, present in both chains, the fork diverged from.
+    pub fn fork_root(&self) -> &BlockHash {
+        &self.fork_root
+    }
+
+    /// Height of [`ReorgResult::fork_root`].
+    pub fn height_when_fork(&self) -> u64 {
+        self.height_when_fork
+    }
+
+    /// Blocks that were discarded from the old chain.
+    pub fn discarded_blocks(&self) -> &[BlockHash] {
+        &self.discarded_blocks
+    }
+
+    /// Bags that lost confirmation but were re-confirmed on the new chain.
+    pub fn reconfirmed_bags(&self) -> &[BagId] {
+        &self.reconfirmed_bags
+    }
+
+    /// Bags that lost confirmation and are still orphaned on the new chain.
+    pub fn orphaned_bags(&self) -> &[BagId] {
+        &self.orphaned_bags
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum TrackerError<C: Error, S: Error> {
     #[error(transparent)]
@@ -278,6 +733,9 @@ pub enum TrackerError<C: Error, S: Error> {
     #[error(transparent)]
     StorageError(#[from] BidStorageError<S>),
 
+    #[error("Checkpoint storage error: {0}")]
+    ChainError(String),
+
     #[error("Transaction with {1} id does not contains in block with {0} id.")]
     TxDoesNotExists(BlockHash, Txid),
 
@@ -351,7 +809,11 @@ mod tests {
 
         index.add_bid(prf1).unwrap();
         index
-            .add_bid(BidProof::new(block2.block_hash, prf2))
+            .add_bid(BidProof::new(
+                bitcoin::Network::Regtest,
+                block2.block_hash,
+                prf2,
+            ))
             .unwrap();
         assert_eq!(index.bids_storage.get_blocks_count().unwrap(), 1);
 
@@ -0,0 +1,65 @@
+use bitcoin::{Block, BlockHash, Transaction, Txid};
+use std::collections::HashMap;
+
+/// A transaction paired with its `Txid`, computed once at construction so scanning does
+/// not repeatedly pay for the double-SHA256.
+#[derive(Debug, Clone)]
+pub struct IndexedTransaction {
+    pub txid: Txid,
+    pub raw: Transaction,
+}
+
+impl From<Transaction> for IndexedTransaction {
+    fn from(raw: Transaction) -> Self {
+        IndexedTransaction {
+            txid: raw.txid(),
+            raw,
+        }
+    }
+}
+
+/// A block whose transactions carry their precomputed `Txid`s (kept parallel to the
+/// transaction list) plus an index from `Txid` to position, so locating a transaction is a
+/// hashmap lookup rather than a rehash-and-scan.
+///
+/// The same block fetched in both `add_bid` and reorg handling can then be wrapped once and
+/// reused without reparsing or rehashing.
+#[derive(Debug, Clone)]
+pub struct IndexedBlock {
+    pub block_hash: BlockHash,
+    transactions: Vec<IndexedTransaction>,
+    by_txid: HashMap<Txid, usize>,
+}
+
+impl IndexedBlock {
+    pub fn block_hash(&self) -> BlockHash {
+        self.block_hash
+    }
+
+    pub fn transactions(&self) -> &[IndexedTransaction] {
+        &self.transactions
+    }
+
+    /// The transaction with the given id, if present.
+    pub fn transaction(&self, txid: &Txid) -> Option<&IndexedTransaction> {
+        self.by_txid.get(txid).map(|&i| &self.transactions[i])
+    }
+}
+
+impl From<Block> for IndexedBlock {
+    fn from(block: Block) -> Self {
+        let block_hash = block.block_hash();
+        let mut by_txid = HashMap::with_capacity(block.txdata.len());
+        let mut transactions = Vec::with_capacity(block.txdata.len());
+        for raw in block.txdata {
+            let tx = IndexedTransaction::from(raw);
+            by_txid.insert(tx.txid, transactions.len());
+            transactions.push(tx);
+        }
+        IndexedBlock {
+            block_hash,
+            transactions,
+            by_txid,
+        }
+    }
+}
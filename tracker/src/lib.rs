@@ -1,7 +1,17 @@
+#[cfg(feature = "async")]
+pub mod async_client;
+pub mod bag_id;
 pub mod bitcoin_client;
+pub mod chain;
+pub mod chain_params;
+pub mod compact_filter;
+#[cfg(feature = "esplora")]
+pub mod esplora;
 pub mod index;
+pub mod indexed;
 pub mod record;
 pub mod storage;
+pub mod sync;
 
 pub use index::Index;
 
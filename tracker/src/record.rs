@@ -1,27 +1,64 @@
 use crate::bag_id::BagId;
-use bitcoin::{BlockHash, Txid};
+use crate::chain_params::{Bitcoin, ChainParams};
+use bitcoin::consensus::encode::{self, Decodable, Encodable};
+use bitcoin::hashes::{sha256d, Hash as _};
+use bitcoin::{BlockHash, Network, TxMerkleNode, Txid, VarInt};
+use std::convert::TryFrom;
 use std::hash::Hash;
+use std::io;
 
 /// Bid entry with full data.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub struct BidEntry {
+pub struct BidEntry<C: ChainParams = Bitcoin> {
     pub amount: u64,
-    pub proof: BidProof,
+    pub proof: BidProof<C>,
 }
 
-/// Bitcoin outpoint that contains txid and output position.
+/// Outpoint that contains txid and output position, generic over the chain backend.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub struct Outpoint {
-    pub txid: Txid,
+pub struct Outpoint<C: ChainParams = Bitcoin> {
+    pub txid: C::Txid,
     pub out_pos: u64,
 }
 
-impl Outpoint {
-    pub fn new(txid: Txid, out_pos: u64) -> Self {
+impl<C: ChainParams> Outpoint<C> {
+    pub fn new(txid: C::Txid, out_pos: u64) -> Self {
         Outpoint { txid, out_pos }
     }
 }
 
+impl Outpoint<Bitcoin> {
+    /// Convert to rust-bitcoin's [`bitcoin::OutPoint`], whose `vout` is a `u32`.
+    ///
+    /// Errors with [`OutpointError::VoutOutOfRange`] instead of silently truncating when
+    /// `out_pos` does not fit in a `u32`, so bridging a `BidTx` to the bitcoin crate can't
+    /// quietly corrupt the output index.
+    pub fn to_bitcoin_outpoint(&self) -> Result<bitcoin::OutPoint, OutpointError> {
+        let vout = u32::try_from(self.out_pos)
+            .map_err(|_| OutpointError::VoutOutOfRange(self.out_pos))?;
+        Ok(bitcoin::OutPoint {
+            txid: self.txid,
+            vout,
+        })
+    }
+}
+
+impl From<bitcoin::OutPoint> for Outpoint<Bitcoin> {
+    fn from(outpoint: bitcoin::OutPoint) -> Self {
+        Outpoint {
+            txid: outpoint.txid,
+            out_pos: u64::from(outpoint.vout),
+        }
+    }
+}
+
+/// Error converting an [`Outpoint`] to rust-bitcoin's [`bitcoin::OutPoint`].
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum OutpointError {
+    #[error("output position {0} does not fit in a u32 vout")]
+    VoutOutOfRange(u64),
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct BidEntryData {
     pub bag_id: BagId,
@@ -30,26 +67,426 @@ pub struct BidEntryData {
 
 /// Proof that contains all information needed to check existence of bid.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub struct BidProof {
-    pub btc_block: BlockHash,
-    pub tx: BidTx,
+pub struct BidProof<C: ChainParams = Bitcoin> {
+    /// Chain the proof's hashes belong to. `BlockHash`/`Txid` carry no network tag of their
+    /// own, so without this a testnet proof and a mainnet proof are indistinguishable and a
+    /// bid could be replayed across chains.
+    pub network: Network,
+    pub btc_block: C::BlockHash,
+    pub tx: BidTx<C>,
+    /// SPV inclusion proof of `tx` within `btc_block`, when available.
+    ///
+    /// It lets a consumer check the bid was actually mined without trusting the indexer;
+    /// a bare `(txid, block)` tuple only the indexer can vouch for when this is `None`.
+    pub merkle_proof: Option<MerkleProof>,
+}
+
+impl<C: ChainParams> BidProof<C> {
+    pub fn new(network: Network, btc_block: C::BlockHash, tx: BidTx<C>) -> Self {
+        BidProof {
+            network,
+            btc_block,
+            tx,
+            merkle_proof: None,
+        }
+    }
+
+    pub fn with_merkle_proof(
+        network: Network,
+        btc_block: C::BlockHash,
+        tx: BidTx<C>,
+        merkle_proof: MerkleProof,
+    ) -> Self {
+        BidProof {
+            network,
+            btc_block,
+            tx,
+            merkle_proof: Some(merkle_proof),
+        }
+    }
+
+    /// Whether the proof declares the chain the verifier expects. A mismatch means the proof
+    /// was built for a different network and must not be trusted.
+    pub fn verify_network(&self, expected: Network) -> bool {
+        self.network == expected
+    }
+}
+
+impl BidProof<Bitcoin> {
+    /// Check the attached inclusion proof: recompute the Merkle root from the `BidTx`'s txid
+    /// up the branch and compare it with the root the proof commits to. Returns `false` when
+    /// no proof is attached.
+    ///
+    /// This turns `BidProof` from a trusted blob into a standalone SPV proof; the caller
+    /// should still compare [`MerkleProof::merkle_root`] against the `merkleroot` from
+    /// `get_block_header_info` for `btc_block`.
+    pub fn verify(&self) -> bool {
+        match &self.merkle_proof {
+            Some(proof) => proof.verify(&self.tx.outpoint.txid),
+            None => false,
+        }
+    }
+
+    /// Verify the inclusion proof against an externally-supplied Merkle root, rejecting the
+    /// proof when it was built for a network other than `expected`.
+    pub fn verify_on_network(&self, expected: Network, merkle_root: &TxMerkleNode) -> bool {
+        self.verify_network(expected) && self.verify_inclusion(merkle_root)
+    }
+
+    /// Check the inclusion proof against an externally-supplied Merkle root.
+    pub fn verify_inclusion(&self, merkle_root: &TxMerkleNode) -> bool {
+        self.verify() && self.merkle_proof.as_ref().map(|p| p.merkle_root) == Some(*merkle_root)
+    }
+}
+
+/// An SPV Merkle branch proving a transaction's inclusion in a block.
+///
+/// `merkle_branch` holds the sibling hashes from the leaf up to the root; `tx_index` is the
+/// transaction's position in the block, whose bits (LSB first) tell, at each level, whether
+/// the running hash is the left (`0`) or right (`1`) child. `merkle_root` is the value the
+/// branch must reproduce.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MerkleProof {
+    pub merkle_branch: Vec<TxMerkleNode>,
+    pub tx_index: u32,
+    pub merkle_root: TxMerkleNode,
 }
 
-impl BidProof {
-    pub fn new(btc_block: BlockHash, tx: BidTx) -> Self {
-        BidProof { btc_block, tx }
+impl MerkleProof {
+    pub fn new(merkle_branch: Vec<TxMerkleNode>, tx_index: u32, merkle_root: TxMerkleNode) -> Self {
+        MerkleProof {
+            merkle_branch,
+            tx_index,
+            merkle_root,
+        }
+    }
+
+    /// Recompute the root from `txid` up the branch and compare it with `merkle_root`.
+    ///
+    /// At each level, the low bit of the (progressively right-shifted) `tx_index` decides
+    /// child order, and the parent is the double-SHA256 of the concatenated children in
+    /// internal (little-endian) byte order.
+    ///
+    /// The branch must be non-empty: a bare leaf equal to the root would let a 64-byte
+    /// transaction be mistaken for an inner node (the known Merkle-tree malleability), so
+    /// the caller must have confirmed the proven item is a real transaction.
+    pub fn verify(&self, txid: &Txid) -> bool {
+        if self.merkle_branch.is_empty() {
+            return false;
+        }
+        let mut current = TxMerkleNode::from_hash(txid.as_hash());
+        let mut index = self.tx_index;
+        for sibling in &self.merkle_branch {
+            current = if index & 1 == 0 {
+                merkle_parent(&current, sibling)
+            } else {
+                merkle_parent(sibling, &current)
+            };
+            index >>= 1;
+        }
+        current == self.merkle_root
     }
 }
 
-/// Bid information extracted from bitcoin transaction.
+fn merkle_parent(left: &TxMerkleNode, right: &TxMerkleNode) -> TxMerkleNode {
+    let mut engine = sha256d::Hash::engine();
+    use bitcoin::hashes::HashEngine;
+    engine.input(left.as_ref());
+    engine.input(right.as_ref());
+    TxMerkleNode::from_hash(sha256d::Hash::from_engine(engine))
+}
+
+/// Bid information extracted from a chain transaction.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub struct BidTx {
-    pub outpoint: Outpoint,
+pub struct BidTx<C: ChainParams = Bitcoin> {
+    pub outpoint: Outpoint<C>,
     pub bag_id: BagId,
 }
 
-impl BidTx {
-    pub fn new(outpoint: Outpoint, bag_id: BagId) -> Self {
+impl<C: ChainParams> BidTx<C> {
+    pub fn new(outpoint: Outpoint<C>, bag_id: BagId) -> Self {
         BidTx { outpoint, bag_id }
     }
 }
+
+// Canonical wire encoding for the bid proof types, mirroring rust-bitcoin's consensus
+// (de)serialization so these structures can be persisted in a DB or gossiped between nodes
+// independently of their `Debug` formatting. `out_pos` is kept as a full `u64` on the wire
+// (rather than rust-bitcoin's 32-bit vout) so the encoding is lossless for `Outpoint`.
+
+impl<C: ChainParams> Encodable for Outpoint<C>
+where
+    C::Txid: Encodable,
+{
+    fn consensus_encode<W: io::Write>(&self, mut writer: W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += self.txid.consensus_encode(&mut writer)?;
+        len += self.out_pos.consensus_encode(&mut writer)?;
+        Ok(len)
+    }
+}
+
+impl<C: ChainParams> Decodable for Outpoint<C>
+where
+    C::Txid: Decodable,
+{
+    fn consensus_decode<R: io::Read>(mut reader: R) -> Result<Self, encode::Error> {
+        Ok(Outpoint {
+            txid: Decodable::consensus_decode(&mut reader)?,
+            out_pos: Decodable::consensus_decode(&mut reader)?,
+        })
+    }
+}
+
+impl<C: ChainParams> Encodable for BidTx<C>
+where
+    C::Txid: Encodable,
+{
+    fn consensus_encode<W: io::Write>(&self, mut writer: W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += self.outpoint.consensus_encode(&mut writer)?;
+        writer.write_all(&self.bag_id.0)?;
+        len += self.bag_id.0.len();
+        Ok(len)
+    }
+}
+
+impl<C: ChainParams> Decodable for BidTx<C>
+where
+    C::Txid: Decodable,
+{
+    fn consensus_decode<R: io::Read>(mut reader: R) -> Result<Self, encode::Error> {
+        let outpoint = Decodable::consensus_decode(&mut reader)?;
+        let mut bag_id = [0u8; 32];
+        reader.read_exact(&mut bag_id)?;
+        Ok(BidTx {
+            outpoint,
+            bag_id: BagId(bag_id),
+        })
+    }
+}
+
+impl Encodable for MerkleProof {
+    fn consensus_encode<W: io::Write>(&self, mut writer: W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += VarInt(self.merkle_branch.len() as u64).consensus_encode(&mut writer)?;
+        for node in &self.merkle_branch {
+            len += node.consensus_encode(&mut writer)?;
+        }
+        len += self.tx_index.consensus_encode(&mut writer)?;
+        len += self.merkle_root.consensus_encode(&mut writer)?;
+        Ok(len)
+    }
+}
+
+impl Decodable for MerkleProof {
+    fn consensus_decode<R: io::Read>(mut reader: R) -> Result<Self, encode::Error> {
+        let count = VarInt::consensus_decode(&mut reader)?.0;
+        let mut merkle_branch = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            merkle_branch.push(Decodable::consensus_decode(&mut reader)?);
+        }
+        Ok(MerkleProof {
+            merkle_branch,
+            tx_index: Decodable::consensus_decode(&mut reader)?,
+            merkle_root: Decodable::consensus_decode(&mut reader)?,
+        })
+    }
+}
+
+impl<C: ChainParams> Encodable for BidProof<C>
+where
+    C::Txid: Encodable,
+    C::BlockHash: Encodable,
+{
+    fn consensus_encode<W: io::Write>(&self, mut writer: W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += self.network.magic().consensus_encode(&mut writer)?;
+        len += self.btc_block.consensus_encode(&mut writer)?;
+        len += self.tx.consensus_encode(&mut writer)?;
+        match &self.merkle_proof {
+            Some(proof) => {
+                len += 1u8.consensus_encode(&mut writer)?;
+                len += proof.consensus_encode(&mut writer)?;
+            }
+            None => len += 0u8.consensus_encode(&mut writer)?,
+        }
+        Ok(len)
+    }
+}
+
+impl<C: ChainParams> Decodable for BidProof<C>
+where
+    C::Txid: Decodable,
+    C::BlockHash: Decodable,
+{
+    fn consensus_decode<R: io::Read>(mut reader: R) -> Result<Self, encode::Error> {
+        let magic = u32::consensus_decode(&mut reader)?;
+        let network = Network::from_magic(magic)
+            .ok_or(encode::Error::ParseFailed("unknown network magic in BidProof"))?;
+        let btc_block = Decodable::consensus_decode(&mut reader)?;
+        let tx = Decodable::consensus_decode(&mut reader)?;
+        let merkle_proof = match u8::consensus_decode(&mut reader)? {
+            0 => None,
+            1 => Some(Decodable::consensus_decode(&mut reader)?),
+            _ => {
+                return Err(encode::Error::ParseFailed(
+                    "invalid BidProof merkle-proof presence flag",
+                ))
+            }
+        };
+        Ok(BidProof {
+            network,
+            btc_block,
+            tx,
+            merkle_proof,
+        })
+    }
+}
+
+impl<C: ChainParams> Encodable for BidEntry<C>
+where
+    C::Txid: Encodable,
+    C::BlockHash: Encodable,
+{
+    fn consensus_encode<W: io::Write>(&self, mut writer: W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += self.amount.consensus_encode(&mut writer)?;
+        len += self.proof.consensus_encode(&mut writer)?;
+        Ok(len)
+    }
+}
+
+impl<C: ChainParams> Decodable for BidEntry<C>
+where
+    C::Txid: Decodable,
+    C::BlockHash: Decodable,
+{
+    fn consensus_decode<R: io::Read>(mut reader: R) -> Result<Self, encode::Error> {
+        Ok(BidEntry {
+            amount: Decodable::consensus_decode(&mut reader)?,
+            proof: Decodable::consensus_decode(&mut reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::consensus::{deserialize, serialize};
+    use bitcoin::hashes::Hash as _;
+
+    fn sample_proof(with_merkle: bool) -> BidProof {
+        let txid = Txid::from_inner([7u8; 32]);
+        let tx = BidTx::new(Outpoint::new(txid, 0xffff_ffff_ff), BagId([3u8; 32]));
+        let block = BlockHash::from_inner([9u8; 32]);
+        if with_merkle {
+            let proof = MerkleProof::new(
+                vec![
+                    TxMerkleNode::from_inner([1u8; 32]),
+                    TxMerkleNode::from_inner([2u8; 32]),
+                ],
+                0b10,
+                TxMerkleNode::from_inner([4u8; 32]),
+            );
+            BidProof::with_merkle_proof(Network::Bitcoin, block, tx, proof)
+        } else {
+            BidProof::new(Network::Bitcoin, block, tx)
+        }
+    }
+
+    #[test]
+    fn verify_rejects_wrong_network() {
+        let txid = Txid::from_inner([7u8; 32]);
+        let sibling = TxMerkleNode::from_inner([8u8; 32]);
+        let root = merkle_parent(&TxMerkleNode::from_hash(txid.as_hash()), &sibling);
+        let tx = BidTx::new(Outpoint::new(txid, 0), BagId([3u8; 32]));
+        let proof = BidProof::with_merkle_proof(
+            Network::Bitcoin,
+            BlockHash::from_inner([9u8; 32]),
+            tx,
+            MerkleProof::new(vec![sibling], 0, root),
+        );
+        assert!(proof.verify_network(Network::Bitcoin));
+        assert!(!proof.verify_network(Network::Testnet));
+        // A structurally valid proof still fails verification on the wrong network.
+        assert!(proof.verify_on_network(Network::Bitcoin, &root));
+        assert!(!proof.verify_on_network(Network::Testnet, &root));
+    }
+
+    #[test]
+    fn verify_multi_level_branch_with_odd_index() {
+        // Four-leaf tree; prove the leaf at index 1 so the first step takes the
+        // sibling-on-left (`index & 1 == 1`) branch and the second takes the left branch,
+        // exercising both orderings against a known root.
+        let txid = Txid::from_inner([0x11; 32]);
+        let leaf = TxMerkleNode::from_hash(txid.as_hash());
+        let l0 = TxMerkleNode::from_inner([0x22; 32]);
+        let l2 = TxMerkleNode::from_inner([0x33; 32]);
+        let l3 = TxMerkleNode::from_inner([0x44; 32]);
+
+        // Our tx is the right child of the first inner node (index 1 within its pair).
+        let n0 = merkle_parent(&l0, &leaf);
+        let n1 = merkle_parent(&l2, &l3);
+        let root = merkle_parent(&n0, &n1);
+
+        let proof = MerkleProof::new(vec![l0, n1], 1, root);
+        assert!(proof.verify(&txid));
+
+        // The same siblings with an even index swap the concatenation order, so a proof that
+        // got left/right wrong must not reproduce the root.
+        let wrong_order = MerkleProof::new(vec![l0, n1], 0, root);
+        assert!(!wrong_order.verify(&txid));
+    }
+
+    #[test]
+    fn outpoint_round_trip() {
+        let outpoint = Outpoint::new(Txid::from_inner([5u8; 32]), 0x1_0000_0000);
+        let bytes = serialize(&outpoint);
+        assert_eq!(deserialize::<Outpoint>(&bytes).unwrap(), outpoint);
+    }
+
+    #[test]
+    fn bid_tx_round_trip() {
+        let tx = sample_proof(false).tx;
+        let bytes = serialize(&tx);
+        assert_eq!(deserialize::<BidTx>(&bytes).unwrap(), tx);
+    }
+
+    #[test]
+    fn bid_proof_round_trip_with_and_without_merkle() {
+        for with_merkle in [false, true] {
+            let proof = sample_proof(with_merkle);
+            let bytes = serialize(&proof);
+            assert_eq!(deserialize::<BidProof>(&bytes).unwrap(), proof);
+        }
+    }
+
+    #[test]
+    fn bitcoin_outpoint_round_trip() {
+        let txid = Txid::from_inner([5u8; 32]);
+        let bitcoin_outpoint = bitcoin::OutPoint { txid, vout: 7 };
+        let outpoint = Outpoint::from(bitcoin_outpoint);
+        assert_eq!(outpoint.out_pos, 7);
+        assert_eq!(outpoint.to_bitcoin_outpoint().unwrap(), bitcoin_outpoint);
+    }
+
+    #[test]
+    fn bitcoin_outpoint_rejects_oversized_vout() {
+        let outpoint = Outpoint::new(Txid::from_inner([0u8; 32]), u64::from(u32::MAX) + 1);
+        assert_eq!(
+            outpoint.to_bitcoin_outpoint(),
+            Err(OutpointError::VoutOutOfRange(u64::from(u32::MAX) + 1))
+        );
+    }
+
+    #[test]
+    fn bid_entry_round_trip() {
+        let entry = BidEntry {
+            amount: 21_000_000,
+            proof: sample_proof(true),
+        };
+        let bytes = serialize(&entry);
+        assert_eq!(deserialize::<BidEntry>(&bytes).unwrap(), entry);
+    }
+}
@@ -14,6 +14,17 @@ pub trait IndexStorage {
     fn get_blocks_count(&self) -> Result<u64, Self::Err>;
     fn remove_with_block_hash(&self, hash: &BlockHash) -> Result<(), Self::Err>;
     fn get_blocks_by_hash(&self, hash: &BlockHash) -> Result<Vec<Record>, Self::Err>;
+
+    /// Look up every record that confirms `bag`, across all blocks that contain it.
+    ///
+    /// This answers the core question a mint consumer asks — "where/if is bag X
+    /// confirmed?" — without scanning every block.
+    fn get_records_by_bag_id(&self, bag: &BagId) -> Result<Vec<Record>, Self::Err>;
+
+    /// Whether any record confirms `bag`.
+    fn contains_bag(&self, bag: &BagId) -> Result<bool, Self::Err> {
+        Ok(!self.get_records_by_bag_id(bag)?.is_empty())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
@@ -31,11 +42,18 @@ pub struct RecordData {
 }
 
 #[derive(Debug)]
-pub struct MemoryIndexStorage(RefCell<HashMap<BlockHash, Vec<Record>>>);
+pub struct MemoryIndexStorage {
+    blocks: RefCell<HashMap<BlockHash, Vec<Record>>>,
+    // Secondary index: the blocks each bag is confirmed in, kept in sync with `blocks`.
+    by_bag: RefCell<HashMap<BagId, Vec<BlockHash>>>,
+}
 
 impl MemoryIndexStorage {
     pub fn new() -> Self {
-        MemoryIndexStorage(RefCell::new(HashMap::new()))
+        MemoryIndexStorage {
+            blocks: RefCell::new(HashMap::new()),
+            by_bag: RefCell::new(HashMap::new()),
+        }
     }
 }
 
@@ -43,24 +61,55 @@ impl IndexStorage for MemoryIndexStorage {
     type Err = Infallible;
 
     fn store_record(&self, record: Record) -> Result<(), Self::Err> {
-        let mut this = self.0.borrow_mut();
-        let vec = this.entry(record.bitcoin_block).or_default();
-        vec.push(record);
+        let block = record.bitcoin_block;
+        let bag = record.data.bag_id;
+        self.blocks.borrow_mut().entry(block).or_default().push(record);
+        // One entry per block, so a bag with several records in a block is not listed twice.
+        let mut by_bag = self.by_bag.borrow_mut();
+        let blocks = by_bag.entry(bag).or_default();
+        if !blocks.contains(&block) {
+            blocks.push(block);
+        }
         Ok(())
     }
 
     fn get_blocks_count(&self) -> Result<u64, Self::Err> {
-        Ok(self.0.borrow().len() as u64)
+        Ok(self.blocks.borrow().len() as u64)
     }
 
     fn remove_with_block_hash(&self, hash: &BlockHash) -> Result<(), Self::Err> {
-        self.0.borrow_mut().remove(hash);
+        if let Some(records) = self.blocks.borrow_mut().remove(hash) {
+            let mut by_bag = self.by_bag.borrow_mut();
+            for record in records {
+                if let Some(blocks) = by_bag.get_mut(&record.data.bag_id) {
+                    blocks.retain(|b| b != hash);
+                    if blocks.is_empty() {
+                        by_bag.remove(&record.data.bag_id);
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
     fn get_blocks_by_hash(&self, hash: &BlockHash) -> Result<Vec<Record>, Self::Err> {
-        let this = self.0.borrow();
+        let this = self.blocks.borrow();
         let records = this.get(hash).map(Clone::clone).unwrap();
         Ok(records)
     }
+
+    fn get_records_by_bag_id(&self, bag: &BagId) -> Result<Vec<Record>, Self::Err> {
+        let by_bag = self.by_bag.borrow();
+        let blocks = self.blocks.borrow();
+        let records = by_bag
+            .get(bag)
+            .into_iter()
+            .flatten()
+            .filter_map(|hash| blocks.get(hash))
+            .flatten()
+            .filter(|record| record.data.bag_id == *bag)
+            .cloned()
+            .collect();
+        Ok(records)
+    }
 }
@@ -1,6 +1,6 @@
 use crate::bag_id::BagId;
-use crate::record::BidEntry;
-use bitcoin::BlockHash;
+use crate::record::{BidEntry, Outpoint};
+use bitcoin::{BlockHash, Txid};
 use std::error::Error;
 use thiserror::Error;
 
@@ -29,7 +29,63 @@ pub trait BidStorage {
         hash: &BlockHash,
     ) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>>;
 
+    /// Whether `bag` is known to the storage, confirmed or unconfirmed. This reports a bag
+    /// the instant its output is mined and is **not** gated by the confirmation-depth safety
+    /// margin; a consumer that must not act on shallow, reorg-prone bags has to use
+    /// [`Index::confirmed_bags`](crate::Index::confirmed_bags) instead of this method.
     fn is_bag_exists(&self, bag: &BagId) -> Result<bool, BidStorageError<Self::Err>>;
+
+    /// Look up every confirmed bid for `bag`, so a consumer can answer "where/if is bag X
+    /// confirmed?" directly. The sqlite backend answers from an index on `bag_id`;
+    /// `MemoryIndexStorage` walks every stored block.
+    fn get_records_by_bag_id(
+        &self,
+        bag: &BagId,
+    ) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>>;
+
+    /// Whether any confirmed bid exists for `bag`.
+    fn contains_bag(&self, bag: &BagId) -> Result<bool, BidStorageError<Self::Err>> {
+        Ok(!self.get_records_by_bag_id(bag)?.is_empty())
+    }
+
+    /// Record a bid seen in the mempool but not yet mined. Its `proof.btc_block` is a
+    /// placeholder until the transaction is confirmed and the entry is promoted.
+    fn insert_pending_bid(&self, record: BidEntry) -> Result<(), BidStorageError<Self::Err>>;
+
+    /// Drop a pending bid (it was mined and promoted, or evicted from the mempool).
+    fn remove_pending_bid(&self, txid: &Txid) -> Result<(), BidStorageError<Self::Err>>;
+
+    /// Every currently-pending (unconfirmed) bid.
+    fn pending_bids(&self) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>>;
+
+    /// Every confirmed bid, so the caller can recompute confirmation depth against the tip.
+    fn confirmed_bids(&self) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>>;
+
+    /// The bag whose confirmed bid is backed by `outpoint`, if any. Used to notice when the
+    /// UTXO backing a bid is spent.
+    fn bag_by_outpoint(
+        &self,
+        outpoint: &Outpoint,
+    ) -> Result<Option<BagId>, BidStorageError<Self::Err>>;
+
+    /// Retire the confirmed bid backed by `outpoint` because that UTXO was spent in
+    /// `spending_block`. Retirement keys on the spent outpoint, not on `bag`, so a bag with more
+    /// than one confirmed bid loses only the entry whose output was actually spent. `bag` is
+    /// used only to report [`BidStorageError::BagDoesNotExists`] when no such entry exists. The
+    /// entry is kept (keyed by the spending block) so a reorg that discards that block can
+    /// resurrect it.
+    fn retire_bag(
+        &self,
+        bag: &BagId,
+        outpoint: &Outpoint,
+        spending_block: &BlockHash,
+    ) -> Result<(), BidStorageError<Self::Err>>;
+
+    /// Resurrect every bid retired by a spend in `spending_block` (the block was orphaned).
+    fn resurrect_with_block_hash(
+        &self,
+        spending_block: &BlockHash,
+    ) -> Result<(), BidStorageError<Self::Err>>;
 }
 
 #[derive(Debug, Error)]
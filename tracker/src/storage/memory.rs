@@ -1,8 +1,8 @@
 use crate::index::BagId;
-use crate::record::BidEntry;
+use crate::record::{BidEntry, Outpoint};
 use crate::storage::def::BidStorageError;
 use crate::storage::BidStorage;
-use bitcoin::BlockHash;
+use bitcoin::{BlockHash, Txid};
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
@@ -12,6 +12,9 @@ use std::convert::Infallible;
 pub struct MemoryIndexStorage {
     confirmed: RefCell<HashMap<BlockHash, HashMap<BagId, BidEntry>>>,
     unconfirmed: RefCell<HashSet<BagId>>,
+    pending: RefCell<HashMap<Txid, BidEntry>>,
+    // Bids retired by a spend, keyed by the spending block so a reorg can resurrect them.
+    retired: RefCell<HashMap<BlockHash, Vec<BidEntry>>>,
 }
 
 impl MemoryIndexStorage {
@@ -19,6 +22,8 @@ impl MemoryIndexStorage {
         MemoryIndexStorage {
             confirmed: RefCell::new(HashMap::new()),
             unconfirmed: RefCell::new(HashSet::new()),
+            pending: RefCell::new(HashMap::new()),
+            retired: RefCell::new(HashMap::new()),
         }
     }
 }
@@ -101,6 +106,111 @@ impl BidStorage for MemoryIndexStorage {
         Ok(self.is_bag_confirmed(bag)? || self.unconfirmed.borrow().contains(bag))
     }
 
+    fn get_records_by_bag_id(
+        &self,
+        bag: &BagId,
+    ) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>> {
+        let records = self
+            .confirmed
+            .borrow()
+            .values()
+            .filter_map(|bids| bids.get(bag).cloned())
+            .collect();
+        Ok(records)
+    }
+
+    fn insert_pending_bid(&self, record: BidEntry) -> Result<(), BidStorageError<Self::Err>> {
+        self.pending
+            .borrow_mut()
+            .insert(record.proof.tx.outpoint.txid, record);
+        Ok(())
+    }
+
+    fn remove_pending_bid(&self, txid: &Txid) -> Result<(), BidStorageError<Self::Err>> {
+        self.pending.borrow_mut().remove(txid);
+        Ok(())
+    }
+
+    fn pending_bids(&self) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>> {
+        Ok(self.pending.borrow().values().cloned().collect())
+    }
+
+    fn confirmed_bids(&self) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>> {
+        Ok(self
+            .confirmed
+            .borrow()
+            .values()
+            .flat_map(|bids| bids.values().cloned())
+            .collect())
+    }
+
+    fn bag_by_outpoint(
+        &self,
+        outpoint: &Outpoint,
+    ) -> Result<Option<BagId>, BidStorageError<Self::Err>> {
+        Ok(self
+            .confirmed
+            .borrow()
+            .values()
+            .flat_map(|bids| bids.values())
+            .find(|bid| bid.proof.tx.outpoint == *outpoint)
+            .map(|bid| bid.proof.tx.bag_id))
+    }
+
+    fn retire_bag(
+        &self,
+        bag: &BagId,
+        outpoint: &Outpoint,
+        spending_block: &BlockHash,
+    ) -> Result<(), BidStorageError<Self::Err>> {
+        let entry = {
+            let mut confirmed = self.confirmed.borrow_mut();
+            let mut found = None;
+            let mut empty_block = None;
+            for (block, bids) in confirmed.iter_mut() {
+                // Match the exact confirmed entry whose backing output was spent.
+                let key = bids
+                    .iter()
+                    .find(|(_, entry)| entry.proof.tx.outpoint == *outpoint)
+                    .map(|(bag_id, _)| *bag_id);
+                if let Some(key) = key {
+                    found = bids.remove(&key);
+                    if bids.is_empty() {
+                        empty_block = Some(*block);
+                    }
+                    break;
+                }
+            }
+            if let Some(block) = empty_block {
+                confirmed.remove(&block);
+            }
+            found
+        };
+        match entry {
+            Some(entry) => {
+                self.retired
+                    .borrow_mut()
+                    .entry(*spending_block)
+                    .or_default()
+                    .push(entry);
+                Ok(())
+            }
+            None => Err(BidStorageError::BagDoesNotExists(*bag)),
+        }
+    }
+
+    fn resurrect_with_block_hash(
+        &self,
+        spending_block: &BlockHash,
+    ) -> Result<(), BidStorageError<Self::Err>> {
+        if let Some(entries) = self.retired.borrow_mut().remove(spending_block) {
+            for entry in entries {
+                self.insert_bid(entry)?;
+            }
+        }
+        Ok(())
+    }
+
     fn is_bag_confirmed(&self, bag: &BagId) -> Result<bool, BidStorageError<Self::Err>> {
         Ok(self
             .confirmed
@@ -4,6 +4,7 @@ use crate::storage::def::BidStorageError;
 use crate::storage::BidStorage;
 use bitcoin::hashes::Hash;
 use bitcoin::BlockHash;
+use bitcoin::Network;
 use bitcoin::Txid;
 use rusqlite::Connection;
 use std::convert::TryFrom;
@@ -38,7 +39,30 @@ impl BidSqliteStorage {
              txid BLOB,
              out_pos INTEGER,
              bag_id BLOB NOT NULL,
-             amount INTEGER
+             amount INTEGER,
+             network INTEGER
+         )",
+                [],
+            )
+            .unwrap();
+        // Secondary index so bag-status lookups don't scan the whole table.
+        self.connection
+            .execute(
+                "CREATE INDEX IF NOT EXISTS records_bag_id ON records (bag_id)",
+                [],
+            )
+            .unwrap();
+        // Bids retired by a spend, kept keyed by the spending block so a reorg can restore them.
+        self.connection
+            .execute(
+                "CREATE TABLE IF NOT EXISTS retired (
+             spending_block BLOB NOT NULL,
+             block BLOB,
+             txid BLOB,
+             out_pos INTEGER,
+             bag_id BLOB NOT NULL,
+             amount INTEGER,
+             network INTEGER
          )",
                 [],
             )
@@ -51,13 +75,14 @@ impl BidStorage for BidSqliteStorage {
 
     fn insert_bid(&self, record: BidEntry) -> Result<(), BidStorageError<Self::Err>> {
         self.connection.execute(
-            "INSERT INTO records VALUES (?1, ?2, ?3, ?4, ?5);",
+            "INSERT INTO records VALUES (?1, ?2, ?3, ?4, ?5, ?6);",
             rusqlite::params![
                 record.proof.btc_block.as_ref(),
                 record.proof.tx.outpoint.txid.as_ref(),
                 record.proof.tx.outpoint.out_pos,
                 &record.proof.tx.bag_id,
-                record.amount
+                record.amount,
+                record.proof.network.magic()
             ],
         )?;
         Ok(())
@@ -65,12 +90,13 @@ impl BidStorage for BidSqliteStorage {
 
     fn insert_unconfirmed_bag(&self, bag: BagId) -> Result<(), BidStorageError<Self::Err>> {
         self.connection.execute(
-            "INSERT INTO records VALUES (?1, ?2, ?3, ?4, ?5);",
+            "INSERT INTO records VALUES (?1, ?2, ?3, ?4, ?5, ?6);",
             rusqlite::params![
                 &rusqlite::types::Null,
                 &rusqlite::types::Null,
                 &rusqlite::types::Null,
                 &bag,
+                &rusqlite::types::Null,
                 &rusqlite::types::Null
             ],
         )?;
@@ -136,7 +162,7 @@ impl BidStorage for BidSqliteStorage {
         hash: &BlockHash,
     ) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>> {
         let mut stmt = self.connection.prepare(
-            "SELECT block, txid, out_pos, bag_id, amount FROM records WHERE block = ?1;",
+            "SELECT block, txid, out_pos, bag_id, amount, network FROM records WHERE block = ?1;",
         )?;
 
         let res = stmt.query_map([hash.as_ref()], |row| {
@@ -146,6 +172,7 @@ impl BidStorage for BidSqliteStorage {
                 out_pos: row.get(2)?,
                 bag_id: row.get(3)?,
                 amount: row.get(4)?,
+                network: row.get(5)?,
             })
         });
 
@@ -164,6 +191,176 @@ impl BidStorage for BidSqliteStorage {
             )
             .map_err(Into::into)
     }
+
+    fn get_records_by_bag_id(
+        &self,
+        bag: &BagId,
+    ) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT block, txid, out_pos, bag_id, amount, network FROM records WHERE bag_id = ?1 AND block IS NOT NULL;",
+        )?;
+
+        let res = stmt.query_map([bag], |row| {
+            Ok(BidEntryRaw {
+                btc_block: row.get(0)?,
+                txid: row.get(1)?,
+                out_pos: row.get(2)?,
+                bag_id: row.get(3)?,
+                amount: row.get(4)?,
+                network: row.get(5)?,
+            })
+        });
+
+        let raw = res.and_then(|cursor| cursor.collect::<Result<Vec<_>, _>>())?;
+        raw.into_iter()
+            .map(|raw| raw.try_into_bid().ok_or(BidStorageError::WrongFormat))
+            .collect()
+    }
+
+    fn insert_pending_bid(&self, record: BidEntry) -> Result<(), BidStorageError<Self::Err>> {
+        // Pending rows have a null block until the tx is mined and the entry promoted.
+        self.connection.execute(
+            "INSERT INTO records VALUES (?1, ?2, ?3, ?4, ?5, ?6);",
+            rusqlite::params![
+                &rusqlite::types::Null,
+                record.proof.tx.outpoint.txid.as_ref(),
+                record.proof.tx.outpoint.out_pos,
+                &record.proof.tx.bag_id,
+                record.amount,
+                record.proof.network.magic()
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn remove_pending_bid(&self, txid: &Txid) -> Result<(), BidStorageError<Self::Err>> {
+        self.connection.execute(
+            "DELETE FROM records WHERE txid = ?1 AND block IS NULL;",
+            [txid.as_ref()],
+        )?;
+        Ok(())
+    }
+
+    fn pending_bids(&self) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT txid, out_pos, bag_id, amount, network FROM records WHERE block IS NULL AND txid IS NOT NULL;",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let txid: Vec<u8> = row.get(0)?;
+            let out_pos: u64 = row.get(1)?;
+            let bag_id: Vec<u8> = row.get(2)?;
+            let amount: u64 = row.get(3)?;
+            let network: Option<u32> = row.get(4)?;
+            Ok((txid, out_pos, bag_id, amount, network))
+        })?;
+
+        rows.map(|row| {
+            let (txid, out_pos, bag_id, amount, network) = row?;
+            Ok(BidEntry {
+                amount,
+                proof: BidProof {
+                    network: network
+                        .and_then(Network::from_magic)
+                        .ok_or(BidStorageError::WrongFormat)?,
+                    btc_block: BlockHash::default(),
+                    tx: BidTx {
+                        outpoint: Outpoint {
+                            txid: Txid::from_slice(&txid).map_err(|_| BidStorageError::WrongFormat)?,
+                            out_pos,
+                        },
+                        bag_id: TryFrom::try_from(bag_id.as_slice())
+                            .map_err(|_| BidStorageError::WrongFormat)?,
+                    },
+                    merkle_proof: None,
+                },
+            })
+        })
+        .collect()
+    }
+
+    fn bag_by_outpoint(
+        &self,
+        outpoint: &Outpoint,
+    ) -> Result<Option<BagId>, BidStorageError<Self::Err>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT bag_id FROM records WHERE txid = ?1 AND out_pos = ?2 AND block IS NOT NULL;",
+        )?;
+        let mut rows = stmt.query_map(
+            rusqlite::params![outpoint.txid.as_ref(), outpoint.out_pos],
+            |row| {
+                let bag_id: Vec<u8> = row.get(0)?;
+                Ok(bag_id)
+            },
+        )?;
+        match rows.next() {
+            Some(bag_id) => Ok(Some(
+                TryFrom::try_from(bag_id?.as_slice()).map_err(|_| BidStorageError::WrongFormat)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn retire_bag(
+        &self,
+        bag: &BagId,
+        outpoint: &Outpoint,
+        spending_block: &BlockHash,
+    ) -> Result<(), BidStorageError<Self::Err>> {
+        // Retire the confirmed entry whose backing output was spent, keyed by that outpoint;
+        // other confirmed bids for the same bag and pending (null-block) rows are left in place,
+        // matching `MemoryIndexStorage::retire_bag`.
+        let moved = self.connection.execute(
+            "INSERT INTO retired (spending_block, block, txid, out_pos, bag_id, amount, network)
+             SELECT ?1, block, txid, out_pos, bag_id, amount, network FROM records WHERE txid = ?2 AND out_pos = ?3 AND block IS NOT NULL;",
+            rusqlite::params![spending_block.as_ref(), outpoint.txid.as_ref(), outpoint.out_pos],
+        )?;
+        if moved == 0 {
+            return Err(BidStorageError::BagDoesNotExists(*bag));
+        }
+        self.connection.execute(
+            "DELETE FROM records WHERE txid = ?1 AND out_pos = ?2 AND block IS NOT NULL;",
+            rusqlite::params![outpoint.txid.as_ref(), outpoint.out_pos],
+        )?;
+        Ok(())
+    }
+
+    fn resurrect_with_block_hash(
+        &self,
+        spending_block: &BlockHash,
+    ) -> Result<(), BidStorageError<Self::Err>> {
+        self.connection.execute(
+            "INSERT INTO records (block, txid, out_pos, bag_id, amount, network)
+             SELECT block, txid, out_pos, bag_id, amount, network FROM retired WHERE spending_block = ?1;",
+            [spending_block.as_ref()],
+        )?;
+        self.connection.execute(
+            "DELETE FROM retired WHERE spending_block = ?1;",
+            [spending_block.as_ref()],
+        )?;
+        Ok(())
+    }
+
+    fn confirmed_bids(&self) -> Result<Vec<BidEntry>, BidStorageError<Self::Err>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT block, txid, out_pos, bag_id, amount, network FROM records WHERE block IS NOT NULL;",
+        )?;
+
+        let res = stmt.query_map([], |row| {
+            Ok(BidEntryRaw {
+                btc_block: row.get(0)?,
+                txid: row.get(1)?,
+                out_pos: row.get(2)?,
+                bag_id: row.get(3)?,
+                amount: row.get(4)?,
+                network: row.get(5)?,
+            })
+        });
+
+        let raw = res.and_then(|cursor| cursor.collect::<Result<Vec<_>, _>>())?;
+        raw.into_iter()
+            .map(|raw| raw.try_into_bid().ok_or(BidStorageError::WrongFormat))
+            .collect()
+    }
 }
 
 struct BidEntryRaw {
@@ -172,6 +369,7 @@ struct BidEntryRaw {
     bag_id: Vec<u8>,
     txid: Vec<u8>,
     out_pos: u64,
+    network: Option<u32>,
 }
 
 impl BidEntryRaw {
@@ -179,6 +377,7 @@ impl BidEntryRaw {
         Some(BidEntry {
             amount: self.amount,
             proof: BidProof {
+                network: self.network.and_then(Network::from_magic)?,
                 btc_block: BlockHash::from_slice(&self.btc_block).ok()?,
                 tx: BidTx {
                     outpoint: Outpoint {
@@ -187,6 +386,7 @@ impl BidEntryRaw {
                     },
                     bag_id: TryFrom::try_from(self.bag_id.as_slice()).ok()?,
                 },
+                merkle_proof: None,
             },
         })
     }
@@ -232,6 +432,7 @@ mod tests {
         BidEntry {
             amount,
             proof: BidProof::new(
+                Network::Bitcoin,
                 BlockHash::hash(&block),
                 BidTx::new(Outpoint::new(Txid::hash(&txid), out_pos), BagId(bag_id)),
             ),
@@ -0,0 +1,92 @@
+use bitcoin::BlockHash;
+use std::collections::VecDeque;
+
+/// The stage a scheduled block hash is in, mirroring parity-bitcoin's `HashQueueChain`:
+/// hashes flow `scheduled -> requested -> verifying` and leave the chain once verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashState {
+    /// Header known, block not yet requested.
+    Scheduled,
+    /// Block fetch in flight.
+    Requested,
+    /// Block fetched, awaiting in-order verification.
+    Verifying,
+}
+
+/// Three ordered hash queues driving headers-first synchronization.
+///
+/// Headers are cheap, so the full contiguous range is scheduled up front; a bounded window
+/// is then promoted to `requested` and fetched, and fetched blocks move to `verifying` where
+/// they are parsed in order. The tip only advances as the front of `verifying` is confirmed
+/// contiguous with the prior tip, so a failure mid-range leaves a consistent checkpoint.
+#[derive(Debug, Default)]
+pub struct HashQueueChain {
+    scheduled: VecDeque<BlockHash>,
+    requested: VecDeque<BlockHash>,
+    verifying: VecDeque<BlockHash>,
+}
+
+impl HashQueueChain {
+    pub fn new() -> Self {
+        HashQueueChain {
+            scheduled: VecDeque::new(),
+            requested: VecDeque::new(),
+            verifying: VecDeque::new(),
+        }
+    }
+
+    /// Append a contiguous range of header hashes to the scheduled queue.
+    pub fn schedule(&mut self, hashes: impl IntoIterator<Item = BlockHash>) {
+        self.scheduled.extend(hashes);
+    }
+
+    /// Promote up to `window` scheduled hashes into the requested queue and return them so
+    /// the caller can fetch their blocks.
+    pub fn request_window(&mut self, window: usize) -> Vec<BlockHash> {
+        let count = window.saturating_sub(self.requested.len()).min(self.scheduled.len());
+        let mut batch = Vec::with_capacity(count);
+        for _ in 0..count {
+            if let Some(hash) = self.scheduled.pop_front() {
+                self.requested.push_back(hash);
+                batch.push(hash);
+            }
+        }
+        batch
+    }
+
+    /// Mark the front `count` requested hashes as fetched, moving them to the verifying queue.
+    pub fn mark_verifying(&mut self, count: usize) {
+        for _ in 0..count {
+            if let Some(hash) = self.requested.pop_front() {
+                self.verifying.push_back(hash);
+            }
+        }
+    }
+
+    /// Remove and return the next block hash ready for in-order verification.
+    pub fn next_to_verify(&mut self) -> Option<BlockHash> {
+        self.verifying.pop_front()
+    }
+
+    /// State of `hash`, if it is anywhere in the chain.
+    pub fn state(&self, hash: &BlockHash) -> Option<HashState> {
+        if self.scheduled.contains(hash) {
+            Some(HashState::Scheduled)
+        } else if self.requested.contains(hash) {
+            Some(HashState::Requested)
+        } else if self.verifying.contains(hash) {
+            Some(HashState::Verifying)
+        } else {
+            None
+        }
+    }
+
+    /// Whether every queue is drained.
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty() && self.requested.is_empty() && self.verifying.is_empty()
+    }
+
+    pub fn scheduled_len(&self) -> usize {
+        self.scheduled.len()
+    }
+}
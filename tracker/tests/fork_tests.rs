@@ -58,7 +58,7 @@ fn test_reorg_longest_chain() {
         .send_mint_transaction(SATOSHIES_TO_SEND, &BAG1_12)
         .unwrap();
     let both_block = generate_block(&client1, &address1, &bid1_12.outpoint.txid);
-    let prf1_12 = BidProof::new(both_block, bid1_12);
+    let prf1_12 = BidProof::new(bitcoin::Network::Regtest, both_block, bid1_12);
     // Wait before node2 receive block
     wait!(client2.get_blockchain_info().unwrap().best_block_hash == both_block);
 
@@ -72,7 +72,7 @@ fn test_reorg_longest_chain() {
             .send_mint_transaction(SATOSHIES_TO_SEND, &BAG2_1)
             .unwrap();
         let block = generate_block(&client1, &address1, &bid.outpoint.txid);
-        (block, BidProof::new(block, bid))
+        (block, BidProof::new(bitcoin::Network::Regtest, block, bid))
     };
 
     let (bag2_2block, bag3_2block, prf2_2, prf3_2) = {
@@ -91,8 +91,8 @@ fn test_reorg_longest_chain() {
         (
             bag1block,
             bag2block,
-            BidProof::new(bag1block, bid2),
-            BidProof::new(bag2block, bid3),
+            BidProof::new(bitcoin::Network::Regtest, bag1block, bid2),
+            BidProof::new(bitcoin::Network::Regtest, bag2block, bid3),
         )
     };
 
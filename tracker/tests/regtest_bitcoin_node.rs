@@ -38,7 +38,13 @@ fn test_new_blocks_with_mint_txs<S: BidStorage>(storage: S, dir: &str, offset: u
 
     let mut index = Index::new(client, storage, Some(119)).unwrap();
 
-    index.add_bid(BidProof::new(mint_block, bid_tx)).unwrap();
+    index
+        .add_bid(BidProof::new(
+            bitcoin::Network::Regtest,
+            mint_block,
+            bid_tx,
+        ))
+        .unwrap();
 
     assert_eq!(*index.current_height(), GENERATED_BLOCKS + 1);
 